@@ -1,66 +1,517 @@
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::env;
 use std::env::current_dir;
+use std::fs;
 use std::fs::File;
 use std::io;
+use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
-use std::str::FromStr;
+use std::time::{Duration, Instant};
 use anyhow::Context;
+use arboard::Clipboard;
+use chrono::Local;
 use ratatui::crossterm::event;
-use ratatui::crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
 use ratatui::prelude::*;
-use ratatui::widgets::{Block, Borders, HighlightSpacing, List, ListItem, ListState, Paragraph};
+use ratatui::layout::Flex;
+use ratatui::widgets::{Bar, BarChart, BarGroup, Block, Clear, Gauge, HighlightSpacing, List, ListItem, ListState, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap};
 use ratatui::Terminal;
+use serde_json::json;
 use tui_input::backend::crossterm::EventHandler;
-use tui_input::Input;
-use serde::{Deserialize, Serialize};
+use tui_input::{Input, InputRequest};
+
+use crate::config::{Config, DuplicateNames};
+use crate::counter::{format_amount, format_grouped, format_weighted, parse_amount, parse_duration, rescale, scale_amount, total, weighted_total, Counter, DisplayFormat, MAX_PRECISION};
+use crate::expression::evaluate;
+use crate::save_file::{backup_path, probe_writable, rotate_backups, Format, Goal, SaveFile};
+use crate::theme::Theme;
+
+/// How long input has to be idle before pending changes are written to disk.
+const SAVE_DELAY: Duration = Duration::from_millis(500);
+
+/// How long a message stays in the footer without a key press.
+const MESSAGE_DURATION: Duration = Duration::from_secs(3);
+
+/// How often the loop wakes up without input, to redraw and catch up on anything that changes
+/// with time. Long enough to keep the app idle between ticks.
+const TICK: Duration = Duration::from_millis(250);
+
+/// Increment or decrement presses closer together than this count as holding the key down.
+const HOLD_WINDOW: Duration = Duration::from_millis(300);
+
+/// How many steps shift+→ and shift+← move a counter at once.
+const BIG_STEPS: i64 = 10;
+
+/// How long the first key of a sequence like `gg` waits for the second.
+const SEQUENCE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The smallest terminal that fits an input box, one row of the list, the total and the footer.
+const MIN_WIDTH: u16 = 20;
+const MIN_HEIGHT: u16 = 8;
 
 enum AddingModeSign {
     Positive,
     Negative
 }
 
+enum MultiplyModeOperation {
+    Multiply,
+    Divide,
+}
+
+#[derive(Clone, Copy)]
+enum Limit {
+    Min,
+    Max,
+}
+
+/// The file formats the counters can be exported to, next to the save.
+#[derive(Clone, Copy)]
+enum ExportFormat {
+    Csv,
+    Markdown,
+    /// One JSON object per line, for piping into tools like `jq`.
+    JsonLines,
+}
+
+impl ExportFormat {
+    /// The format for a name typed after `export`.
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "csv" => Some(ExportFormat::Csv),
+            "md" | "markdown" => Some(ExportFormat::Markdown),
+            "jsonl" | "ndjson" => Some(ExportFormat::JsonLines),
+            _ => None,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Markdown => "md",
+            ExportFormat::JsonLines => "jsonl",
+        }
+    }
+}
+
 enum InputMode {
     Normal,
     NewCounter(Input),
     Adding(Input, AddingModeSign),
+    Multiply(Input, MultiplyModeOperation),
+    SetValue(Input),
+    /// Overwriting the value of the counter at the index, in its row of the list.
+    EditInline(Input, usize),
+    Rename(Input, usize),
+    EditStep(Input, usize),
+    SetTarget(Input, usize),
+    SetLimit(Input, usize, Limit),
+    SetGroup(Input, usize),
+    EditNote(Input, usize),
+    EditTags(Input, usize),
+    ConfirmDelete(usize),
+    ConfirmReset,
+    ConfirmClear,
+    ConfirmQuit,
+    /// Typing the name of a save to write the counters to from now on.
+    SaveAs(Input),
+    /// Asking before saving over another file.
+    ConfirmOverwrite(PathBuf),
+    Filter(Input),
+    Command(Input),
+    /// Waiting for the letter to jump to, after `f`.
+    Jump,
+    /// The list of every key, scrolled down by this many lines.
+    Help(usize),
+    Stats,
+    Detail(usize),
+    /// Browsing archived counters, with the selected one.
+    Archive(ListState),
+    /// Picking another save to switch to, from the file names in the save directory.
+    OpenFile(Vec<String>, ListState),
 }
 
-#[derive(Serialize, Deserialize)]
-struct Counter {
-    name: String,
-    count: i64,
+#[derive(Clone, Copy, Default, PartialEq)]
+enum SortMode {
+    #[default]
+    Insertion,
+    NameAscending,
+    CountDescending,
+    /// Most recently changed first, and never changed last.
+    RecentlyChanged,
 }
 
-impl Counter {
-    fn new(name: &str) -> Self {
-        Self {
-            name: name.to_owned(),
-            count: 0,
+impl SortMode {
+    fn next(self) -> Self {
+        match self {
+            SortMode::Insertion => SortMode::NameAscending,
+            SortMode::NameAscending => SortMode::CountDescending,
+            SortMode::CountDescending => SortMode::RecentlyChanged,
+            SortMode::RecentlyChanged => SortMode::Insertion,
         }
     }
-}
-
-impl From<&Counter> for ListItem<'_> {
-    fn from(value: &Counter) -> Self {
-        let line = Line::styled(format!("{}: {}", value.count, value.name), Color::White);
 
-        ListItem::new(line)
+    fn title(self) -> &'static str {
+        match self {
+            SortMode::Insertion => "Counters",
+            SortMode::NameAscending => "Counters (by name)",
+            SortMode::CountDescending => "Counters (by count)",
+            SortMode::RecentlyChanged => "Counters (recently changed)",
+        }
     }
 }
 
+/// A line of the displayed list.
+#[derive(PartialEq)]
+enum Row {
+    /// The name of a group, above its counters, or `None` for the counters without one. Can't be
+    /// selected.
+    Header(Option<String>),
+    /// An index into `counters`.
+    Counter(usize),
+}
+
+#[derive(Default)]
 struct CounterList {
     counters: Vec<Counter>,
+    /// Counters put away from the list, kept so they can be brought back.
+    archived: Vec<Counter>,
+    /// The rows of the list in the order they are displayed. The list state selects rows of this
+    /// view, not counters directly.
+    view: Vec<Row>,
     state: ListState,
+    sort: SortMode,
+    /// Only counters whose name contains this, ignoring case, are displayed.
+    filter: String,
+    /// How many rows fit in the list, as of the last render.
+    page_height: usize,
+    /// Where the list was drawn, including its border, as of the last render.
+    area: Rect,
+    /// Whether moving past either end of the list goes round to the other end.
+    wrap: bool,
 }
 
-impl Default for CounterList {
-    fn default() -> Self {
-        Self {
-            counters: vec![],
-            state: Default::default(),
+impl CounterList {
+    fn new(counters: Vec<Counter>, archived: Vec<Counter>, selected: Option<usize>) -> Self {
+        let mut counter_list = Self { counters, archived, ..Default::default() };
+        counter_list.refresh_view(selected);
+        counter_list
+    }
+
+    /// The row under a point on the screen, if the point is inside the list.
+    fn row_at(&self, column: u16, row: u16) -> Option<usize> {
+        let inner = self.area.inner(Margin::new(1, 1));
+        if !inner.contains(Position::new(column, row)) {
+            return None;
+        }
+        let row = self.state.offset() + usize::from(row - inner.y);
+        (row < self.view.len()).then_some(row)
+    }
+
+    /// Where a row is drawn on screen, if it's scrolled into view. The inverse of `row_at`.
+    fn row_area(&self, row: usize) -> Option<Rect> {
+        let inner = self.area.inner(Margin::new(1, 1));
+        let offset = row.checked_sub(self.state.offset())?;
+        let y = inner.y.checked_add(u16::try_from(offset).ok()?)?;
+        (y < inner.bottom()).then_some(Rect::new(inner.x, y, inner.width, 1))
+    }
+
+    /// The index of the counter on a row, if it isn't a header.
+    fn counter_at(&self, row: usize) -> Option<usize> {
+        match self.view.get(row) {
+            Some(Row::Counter(index)) => Some(*index),
+            _ => None,
+        }
+    }
+
+    /// The index of the selected counter, if the selection points at one.
+    fn selected_index(&self) -> Option<usize> {
+        self.state.selected().and_then(|row| self.counter_at(row))
+    }
+
+    /// The closest row to `row` that holds a counter, looking down first.
+    fn nearest_counter_row(&self, row: usize) -> Option<usize> {
+        (row..self.view.len())
+            .chain((0..row.min(self.view.len())).rev())
+            .find(|row| self.counter_at(*row).is_some())
+    }
+
+    /// Selects the next counter down, skipping headers. Selects the first one if nothing is
+    /// selected, or if the last one is and the list wraps.
+    fn select_next(&mut self) {
+        let from = self.state.selected().map_or(0, |row| row + 1);
+        match (from..self.view.len()).find(|row| self.counter_at(*row).is_some()) {
+            Some(row) => self.state.select(Some(row)),
+            None if self.wrap => self.select_first(),
+            None => {}
+        }
+    }
+
+    fn select_first(&mut self) {
+        if let Some(row) = (0..self.view.len()).find(|row| self.counter_at(*row).is_some()) {
+            self.state.select(Some(row));
+        }
+    }
+
+    fn select_last(&mut self) {
+        if let Some(row) = (0..self.view.len()).rev().find(|row| self.counter_at(*row).is_some()) {
+            self.state.select(Some(row));
+        }
+    }
+
+    /// Selects the next counter down whose name starts with `letter`, ignoring case and wrapping
+    /// around to the top. Leaves the selection alone if there's none.
+    fn select_starting_with(&mut self, letter: char) {
+        let from = self.state.selected().map_or(0, |row| row + 1);
+        let rows = self.view.len();
+        let found = (from..from + rows).map(|row| row % rows).find(|row| {
+            self.counter_at(*row).is_some_and(|index| {
+                self.counters[index].name.chars().next().is_some_and(|first| first.to_lowercase().eq(letter.to_lowercase()))
+            })
+        });
+        if let Some(row) = found {
+            self.state.select(Some(row));
+        }
+    }
+
+    /// Selects the next counter up, skipping headers. Selects the last one if nothing is
+    /// selected.
+    fn select_previous(&mut self) {
+        let to = self.state.selected().unwrap_or(self.view.len()).min(self.view.len());
+        match (0..to).rev().find(|row| self.counter_at(*row).is_some()) {
+            Some(row) => self.state.select(Some(row)),
+            None if self.wrap => self.select_last(),
+            None => {}
+        }
+    }
+
+    /// Moves the selection a page up or down, stopping at the ends of the list.
+    fn select_page(&mut self, down: bool) {
+        if self.view.is_empty() {
+            return;
+        }
+        let page = self.page_height.max(1);
+        let row = self.state.selected().unwrap_or(0).min(self.view.len() - 1);
+        let row = if down { (row + page).min(self.view.len() - 1) } else { row.saturating_sub(page) };
+        self.state.select(self.nearest_counter_row(row));
+    }
+
+    /// Rebuilds the display order after the counters or sort mode changed. `selected` is the
+    /// counter to keep selected; if it's gone, the selection stays on the same row.
+    fn refresh_view(&mut self, selected: Option<usize>) {
+        let filter = self.filter.to_lowercase();
+        let mut view: Vec<usize> = (0..self.counters.len())
+            .filter(|index| self.counters[*index].matches(&filter))
+            .collect();
+        match self.sort {
+            SortMode::Insertion => {}
+            SortMode::NameAscending => view.sort_by_cached_key(|index| self.counters[*index].name.to_lowercase()),
+            SortMode::CountDescending => view.sort_by(|a, b| self.counters[*b].value().total_cmp(&self.counters[*a].value())),
+            SortMode::RecentlyChanged => {
+                view.sort_by_key(|index| Reverse((self.counters[*index].last_modified, self.counters[*index].changed_at)));
+            }
+        }
+        // Pinned counters go first, or first in their group, keeping the sort among themselves.
+        view.sort_by_key(|index| !self.counters[*index].pinned);
+        self.view = self.group_rows(view);
+
+        match selected.and_then(|index| self.view.iter().position(|row| *row == Row::Counter(index))) {
+            Some(row) => self.state.select(Some(row)),
+            None => {
+                let row = self.state.selected().and_then(|row| self.nearest_counter_row(row));
+                self.state.select(row);
+            }
+        }
+    }
+
+    /// Splits sorted counter indices into groups under headers. Groups are in the order they
+    /// first appear, with the ungrouped counters last. Every group gets a header even when the
+    /// filter hides all its counters, so its subtotal is still visible. Lists where no counter
+    /// has a group get no headers at all.
+    fn group_rows(&self, view: Vec<usize>) -> Vec<Row> {
+        if self.counters.iter().all(|counter| counter.group.is_none()) {
+            return view.into_iter().map(Row::Counter).collect();
+        }
+
+        let mut groups: Vec<Option<&str>> = vec![];
+        for index in view.iter().copied().chain(0..self.counters.len()) {
+            let group = self.counters[index].group.as_deref();
+            if !groups.contains(&group) {
+                groups.push(group);
+            }
+        }
+        groups.sort_by_key(|group| group.is_none());
+
+        let mut rows = vec![];
+        for group in groups {
+            rows.push(Row::Header(group.map(str::to_owned)));
+            rows.extend(view.iter().filter(|index| self.counters[**index].group.as_deref() == group).map(|index| Row::Counter(*index)));
+        }
+        rows
+    }
+
+    /// Sum of every counter, along with its precision.
+    fn total(&self) -> (i64, u32) {
+        total(&self.counters.iter().collect::<Vec<_>>())
+    }
+
+    /// Sum of the counters in a group, or of the ungrouped counters for `None`.
+    fn group_total(&self, group: Option<&str>) -> (i64, u32) {
+        total(&self.counters.iter().filter(|counter| counter.group.as_deref() == group).collect::<Vec<_>>())
+    }
+
+    /// Whether a counter other than the one at `except` is called `name`.
+    fn name_taken(&self, name: &str, except: Option<usize>) -> bool {
+        self.counters
+            .iter()
+            .enumerate()
+            .any(|(index, counter)| Some(index) != except && counter.name == name)
+    }
+
+    /// `name`, or if it's taken, `name` followed by the first free number like `name (2)`.
+    fn unique_name(&self, name: &str, except: Option<usize>) -> String {
+        if !self.name_taken(name, except) {
+            return name.to_owned();
+        }
+        (2..)
+            .map(|number| format!("{} ({})", name, number))
+            .find(|candidate| !self.name_taken(candidate, except))
+            .expect("there are fewer counters than numbers")
+    }
+
+    /// The name to give a new or renamed counter, following the `duplicate_names` setting.
+    /// `None` if the name is rejected.
+    fn resolve_name(&self, name: &str, except: Option<usize>, policy: DuplicateNames) -> Option<String> {
+        match policy {
+            DuplicateNames::Allow => Some(name.to_owned()),
+            DuplicateNames::Reject if self.name_taken(name, except) => None,
+            DuplicateNames::Reject => Some(name.to_owned()),
+            DuplicateNames::Suffix => Some(self.unique_name(name, except)),
         }
     }
 }
 
+/// A change to the counter list that can be undone and redone.
+enum Action {
+    SetCount { index: usize, from: i64, to: i64 },
+    Rename { index: usize, from: String, to: String },
+    Insert { index: usize, counter: Counter },
+    Remove { index: usize, counter: Counter },
+    /// Moves the counter at `from` to the neighbouring position `to`.
+    Swap { from: usize, to: usize },
+    /// Swaps out a whole counter, for changes that touch several fields at once.
+    /// Boxed since two counters make it much bigger than the other actions.
+    Replace { index: usize, from: Box<Counter>, to: Box<Counter> },
+    /// Moves a counter from the list to the end of the archive.
+    Archive { index: usize, counter: Counter },
+    /// Moves a counter from the archive back to the end of the list, at `index`.
+    Unarchive { archived: usize, index: usize, counter: Counter },
+    /// Zeroes several counters at once, with the count each had before.
+    ResetAll { from: Vec<(usize, i64)> },
+    /// Deletes several counters at once, with the index each had, in order.
+    Clear { removed: Vec<(usize, Counter)> },
+}
+
+impl Action {
+    /// Where the counter at `index` ends up once this action is applied, if it still exists.
+    fn remap(&self, index: usize) -> Option<usize> {
+        match self {
+            Action::SetCount { .. } | Action::Rename { .. } | Action::Replace { .. } | Action::Unarchive { .. } | Action::ResetAll { .. } => Some(index),
+            Action::Insert { index: inserted, .. } if index >= *inserted => Some(index + 1),
+            Action::Insert { .. } => Some(index),
+            Action::Remove { index: removed, .. } | Action::Archive { index: removed, .. } if index == *removed => None,
+            Action::Remove { index: removed, .. } | Action::Archive { index: removed, .. } if index > *removed => Some(index - 1),
+            Action::Remove { .. } | Action::Archive { .. } => Some(index),
+            Action::Swap { from, to } if index == *from => Some(*to),
+            Action::Swap { from, to } if index == *to => Some(*from),
+            Action::Swap { .. } => Some(index),
+            Action::Clear { removed } if removed.iter().any(|(removed, _)| *removed == index) => None,
+            Action::Clear { removed } => Some(index - removed.iter().filter(|(removed, _)| *removed < index).count()),
+        }
+    }
+
+    /// The index of the counter this action touched, once it has been applied or reverted, or
+    /// `None` if it touched several.
+    fn index(&self, reverted: bool) -> Option<usize> {
+        match self {
+            Action::SetCount { index, .. }
+            | Action::Rename { index, .. }
+            | Action::Insert { index, .. }
+            | Action::Remove { index, .. }
+            | Action::Replace { index, .. }
+            | Action::Archive { index, .. }
+            | Action::Unarchive { index, .. } => Some(*index),
+            Action::Swap { from, .. } if reverted => Some(*from),
+            Action::Swap { to, .. } => Some(*to),
+            Action::ResetAll { .. } | Action::Clear { .. } => None,
+        }
+    }
+
+    fn apply(&self, list: &mut CounterList) {
+        let counters = &mut list.counters;
+        match self {
+            Action::SetCount { index, to, .. } => counters[*index].set_count(*to),
+            Action::Rename { index, to, .. } => counters[*index].name = to.clone(),
+            Action::Insert { index, counter } => counters.insert(*index, counter.clone()),
+            Action::Remove { index, .. } => {
+                counters.remove(*index);
+            }
+            Action::Swap { from, to } => counters.swap(*from, *to),
+            Action::Replace { index, to, .. } => counters[*index] = to.as_ref().clone(),
+            Action::Archive { index, counter } => {
+                counters.remove(*index);
+                list.archived.push(counter.clone());
+            }
+            Action::Unarchive { archived, counter, .. } => {
+                list.archived.remove(*archived);
+                counters.push(counter.clone());
+            }
+            Action::ResetAll { from } => {
+                for (index, _) in from {
+                    counters[*index].set_count(0);
+                }
+            }
+            Action::Clear { removed } => {
+                for (index, _) in removed.iter().rev() {
+                    counters.remove(*index);
+                }
+            }
+        }
+    }
+
+    fn revert(&self, list: &mut CounterList) {
+        let counters = &mut list.counters;
+        match self {
+            Action::SetCount { index, from, .. } => counters[*index].set_count(*from),
+            Action::Rename { index, from, .. } => counters[*index].name = from.clone(),
+            Action::Insert { index, .. } => {
+                counters.remove(*index);
+            }
+            Action::Remove { index, counter } => counters.insert(*index, counter.clone()),
+            Action::Swap { from, to } => counters.swap(*from, *to),
+            Action::Replace { index, from, .. } => counters[*index] = from.as_ref().clone(),
+            Action::Archive { index, counter } => {
+                list.archived.pop();
+                counters.insert(*index, counter.clone());
+            }
+            Action::Unarchive { archived, counter, .. } => {
+                counters.pop();
+                list.archived.insert(*archived, counter.clone());
+            }
+            Action::ResetAll { from } => {
+                for (index, count) in from {
+                    counters[*index].set_count(*count);
+                }
+            }
+            Action::Clear { removed } => {
+                for (index, counter) in removed {
+                    counters.insert(*index, counter.clone());
+                }
+            }
+        }
+    }
+}
 
 enum SaveState {
     DoNotSave,
@@ -71,264 +522,2974 @@ pub(crate) struct App {
     counter_list: CounterList,
     input_mode: InputMode,
     should_exit: bool,
+    /// Set when the counters changed since the last save.
+    dirty: bool,
+    /// Whether changes are written once input goes idle, rather than only on w or exit.
+    autosave: bool,
+    /// The selection as of the last save, so moving it also gets saved.
+    saved_selection: Option<usize>,
     save_state: SaveState,
+    undo_stack: Vec<Action>,
+    redo_stack: Vec<Action>,
+    config: Config,
+    /// Opened on first use, and kept open since some platforms drop copied text along with it.
+    clipboard: Option<Clipboard>,
+    held: Option<Held>,
+    /// Shown in the footer in place of the usual hints, with when it was left, until the next key
+    /// press or for `MESSAGE_DURATION`.
+    message: Option<(String, Instant)>,
+    /// Whether the save has been backed up this session.
+    backed_up: bool,
+    /// Whether each counter's share of the total is shown next to it.
+    show_shares: bool,
+    pending: Option<(Pending, Instant)>,
+    /// Whether the counters are drawn as a bar chart instead of a list.
+    show_chart: bool,
+    /// The most recently deleted counter and where it was.
+    last_deleted: Option<(usize, Counter)>,
+    /// Set when a countdown finishes, to ring the terminal bell.
+    bell: bool,
+    /// When the session started, for the clock.
+    started: Instant,
+    /// Whether the time and how long the session has run are shown in the corner of the list.
+    show_clock: bool,
+    /// How many keys fit in the help at once, for paging through it.
+    help_page: usize,
+    /// Whether the save was opened with `--readonly`, so nothing may change or be written.
+    readonly: bool,
+    theme: Theme,
+    /// A target for the total, shown as a gauge in place of it.
+    goal: Option<Goal>,
+}
+
+/// The first key of a two key sequence, waiting for the second.
+#[derive(Clone, Copy)]
+enum Pending {
+    /// `g`, for `gg`.
+    Top,
+    /// The delete key, for pressing it twice when `vim_delete` is on.
+    Delete,
+}
+
+/// An increment or decrement key being held down, as a run of presses in quick succession.
+struct Held {
+    increment: bool,
+    last_press: Instant,
+    presses: u32,
 }
 
 impl App {
-    pub(crate) fn make_temporary() -> Self {
+    fn new(save_file: SaveFile<'static>, save_state: SaveState, config: Config) -> Self {
+        let mut counter_list = CounterList::new(save_file.counters.into_owned(), save_file.archived.into_owned(), save_file.selected);
+        counter_list.wrap = config.wrap_navigation;
         Self {
-            counter_list: Default::default(),
+            goal: save_file.goal,
+            theme: Theme::new(config.theme, &config.colors),
+            show_clock: config.clock,
+            counter_list,
             input_mode: InputMode::Normal,
             should_exit: false,
-            save_state: SaveState::DoNotSave,
+            dirty: false,
+            autosave: true,
+            saved_selection: save_file.selected,
+            save_state,
+            undo_stack: vec![],
+            redo_stack: vec![],
+            config,
+            clipboard: None,
+            held: None,
+            message: None,
+            backed_up: false,
+            show_shares: false,
+            pending: None,
+            show_chart: false,
+            last_deleted: None,
+            bell: false,
+            help_page: 0,
+            readonly: false,
+            started: Instant::now(),
         }
     }
 
-    pub(crate) fn make_saved(input_name: &str) -> anyhow::Result<Self> {
-        let mut path = current_dir().context("Couldn't get working directory")?;
-        path.push(input_name);
-        path.set_extension("json");
-        let file_exists = Path::exists(&path);
+    /// Whether the terminal should report mouse events, which stops the terminal from selecting
+    /// text itself.
+    pub(crate) fn mouse_enabled(&self) -> bool {
+        self.config.mouse
+    }
 
-        Ok(if file_exists {
-            let file = File::open(&path).context(format!("Failed to open file: {}", path.display()))?;
-            let counters: Vec<Counter> = serde_json::from_reader(file).context(format!("Failed to parse file: {}", path.display()))?;
+    pub(crate) fn make_temporary() -> Self {
+        Self::new(SaveFile::default(), SaveState::DoNotSave, Config::load())
+    }
 
-            Self {
-                counter_list: CounterList{ counters, state: Default::default() },
-                input_mode: InputMode::Normal,
-                should_exit: false,
-                save_state: SaveState::Save(path),
-            }
+    /// The save for a name. Names ending in `.toml` are saved as TOML, and anything else as JSON.
+    /// A name without an extension gets `.json`, and any other extension is kept, so `v1.2` is
+    /// `v1.2`. Export extensions are refused, since exports are written next to the save with its
+    /// name and would overwrite it.
+    ///
+    /// Saves go in the directory named by `TUI_COUNTERS_DIR` if it's set and not empty, with a
+    /// leading `~` standing for the home directory, and otherwise in the working directory. A
+    /// name that's a path, like `data/habits.json`, is taken from the working directory instead.
+    fn save_path(input_name: &str) -> anyhow::Result<PathBuf> {
+        let name = Path::new(input_name);
+        let mut path = if name.components().count() > 1 {
+            current_dir().context("Couldn't get working directory")?
         }
         else {
-            Self {
-                counter_list: CounterList::default(),
-                input_mode: InputMode::Normal,
-                should_exit: false,
-                save_state: SaveState::Save(path),
+            Self::save_dir()?
+        };
+        path.push(name);
+        let extension = path.extension().and_then(|extension| extension.to_str());
+        if let Some(format) = [ExportFormat::Csv, ExportFormat::Markdown, ExportFormat::JsonLines].into_iter().find(|format| extension == Some(format.extension())) {
+            anyhow::bail!("Can't use a .{} file as a save, since exports are written there", format.extension());
+        }
+        if path.extension().is_none() {
+            path.set_extension(Format::of(&path).extension());
+        }
+        Ok(path)
+    }
+
+    fn save_dir() -> anyhow::Result<PathBuf> {
+        match env::var_os("TUI_COUNTERS_DIR").filter(|dir| !dir.is_empty()) {
+            Some(dir) => expand_home(PathBuf::from(dir)),
+            None => current_dir().context("Couldn't get working directory"),
+        }
+    }
+
+    /// The file names of the JSON and TOML files in the save directory, sorted.
+    fn save_names() -> anyhow::Result<Vec<String>> {
+        let dir = Self::save_dir()?;
+        let entries = fs::read_dir(&dir).context(format!("Failed to open directory: {}", dir.display()))?;
+        let mut names = vec![];
+        for entry in entries {
+            let path = entry.context(format!("Failed to open directory: {}", dir.display()))?.path();
+            let is_save = matches!(path.extension().and_then(|extension| extension.to_str()), Some("json" | "toml"));
+            if let (true, true, Some(name)) = (is_save, path.is_file(), path.file_name().and_then(|name| name.to_str())) {
+                names.push(name.to_owned());
             }
-        })
+        }
+        names.sort();
+        Ok(names)
     }
 
-    fn save(&self) -> anyhow::Result<()> {
-        let SaveState::Save(buf) = &self.save_state else {
-            return Ok(());
-        };
+    /// Moves a counter to the end of another save in the save directory, making the save if it
+    /// doesn't exist yet. Undoing only brings back this file's copy.
+    fn move_to_file(&mut self, index: usize, name: &str) -> anyhow::Result<()> {
+        let path = Self::save_path(name)?;
+        if matches!(&self.save_state, SaveState::Save(current) if *current == path) {
+            anyhow::bail!("The counter is already in {}", path.display());
+        }
+        let mut target = if path.exists() { SaveFile::read(&path)? } else { SaveFile::default() };
+        target.counters.to_mut().push(self.counter_list.counters[index].clone());
+        target.write(&path)?;
+
+        self.delete(index);
+        self.flush()?;
+        self.notify(format!("Moved it to {}", path.display()));
+        Ok(())
+    }
 
-        let file = File::create(buf).context(format!("Failed to open file: {}", buf.display()))?;
+    /// Saves the counters under a new name in the save directory, and keeps saving there. An
+    /// existing file there is asked about first.
+    fn start_save_as(&mut self, name: &str) -> anyhow::Result<()> {
+        let path = Self::save_path(name)?;
+        let current = matches!(&self.save_state, SaveState::Save(current) if *current == path);
+        if path.exists() && !current {
+            self.input_mode = InputMode::ConfirmOverwrite(path);
+            return Ok(());
+        }
+        self.save_as(path)
+    }
 
-        serde_json::to_writer_pretty(file, &self.counter_list.counters).context(format!("Failed to open file: {}", buf.display()))?;
+    fn save_as(&mut self, path: PathBuf) -> anyhow::Result<()> {
+        self.input_mode = InputMode::Normal;
+        self.save_state = SaveState::Save(path);
+        // A file being saved over gets backed up like any other save.
+        self.backed_up = false;
+        self.dirty = true;
+        self.flush()
+    }
 
+    /// Saves the current file and switches to another save in the save directory. Settings for
+    /// the session, like autosave, carry over.
+    fn open_file(&mut self, name: &str) -> anyhow::Result<()> {
+        self.flush()?;
+        let mut opened = Self::make_saved(name)?;
+        opened.autosave = self.autosave;
+        opened.show_shares = self.show_shares;
+        opened.started = self.started;
+        opened.show_clock = self.show_clock;
+        opened.show_chart = self.show_chart;
+        opened.clipboard = self.clipboard.take();
+        opened.theme = Theme::new(self.theme.name, &opened.config.colors);
+        *self = opened;
         Ok(())
     }
-    
-    pub(crate) fn run(&mut self, mut terminal: Terminal<impl Backend>) -> io::Result<String> {
-        let mut end_message = String::new();
 
-        while !self.should_exit {
-            terminal.draw(|f| f.render_widget(&mut *self, f.size()))?;
-            if let Event::Key(key) = event::read()? {
-                match self.handle_key(key) {
-                    Ok(_) => {}
-                    Err(error) => {
-                        end_message = error.to_string();
-                    }
-                };
-            };
+    pub(crate) fn make_saved(input_name: &str) -> anyhow::Result<Self> {
+        let path = Self::save_path(input_name)?;
+        let file_exists = Path::exists(&path);
+
+        let save_file = if file_exists {
+            SaveFile::read(&path)?
         }
-        Ok(end_message)
+        else {
+            SaveFile::default()
+        };
+
+        let mut app = Self::new(save_file, SaveState::Save(path), Config::load());
+        app.reset_daily_counters();
+        app.catch_up_timers();
+        Ok(app)
     }
 
-    fn handle_key(&mut self, key: KeyEvent) -> anyhow::Result<()> {
-        if key.kind != KeyEventKind::Press {
-            return Ok(());
+    /// Zeroes the counters that reset daily and haven't yet today, like at the start of a
+    /// session or once a session runs past midnight.
+    fn reset_daily_counters(&mut self) {
+        let mut changed = false;
+        for counter in &mut self.counter_list.counters {
+            changed |= counter.reset_if_new_day();
         }
-        match &mut self.input_mode {
-            InputMode::Normal => match key.code {
-                KeyCode::Up | KeyCode::Char('k') => self.counter_list.state.select_previous(),
-                KeyCode::Down | KeyCode::Char('j') => self.counter_list.state.select_next(),
-                KeyCode::Right | KeyCode::Char('l') => {
-                    match self.counter_list.state.selected() {
-                        Some(index) => match self.counter_list.counters.get_mut(index) {
-                            Some(counter) => counter.count += 1,
-                            None => {}
-                        },
-                        None => {}
-                    }
-                    self.save()?;
-                },
-                KeyCode::Left | KeyCode::Char(';') => {
-                    match self.counter_list.state.selected() {
-                        Some(index) => match self.counter_list.counters.get_mut(index) {
-                            Some(counter) => counter.count -= 1,
-                            None => {}
-                        },
-                        None => {}
-                    }
-                    self.save()?;
-                },
-                KeyCode::Char('q') => self.should_exit = true,
-                KeyCode::Char('n') => self.input_mode = InputMode::NewCounter(Input::default()),
-                KeyCode::Char('d') => {
-                    match self.counter_list.state.selected() {
-                        Some(index) => {
-                            self.counter_list.counters.remove(index);
-                        }
-                        None => {}
-                    }
-                    self.save()?;
-                },
-                KeyCode::Esc => self.counter_list.state.select(None),
-                KeyCode::Char('a') => self.input_mode = InputMode::Adding(Input::default(), AddingModeSign::Positive),
-                KeyCode::Char('s') => self.input_mode = InputMode::Adding(Input::default(), AddingModeSign::Negative),
-                _ => {}
-            },
-            InputMode::NewCounter(input) => match key.code {
-                KeyCode::Esc => self.input_mode = InputMode::Normal,
-                KeyCode::Enter => {
-                    self.counter_list.counters.push(Counter::new(input.value()));
-                    input.reset();
-                    self.save()?;
-                }
-                _ => {
-                    input.handle_event(&Event::Key(key));
-                }
-            },
-            InputMode::Adding(input, sign) => match key.code {
-                KeyCode::Up | KeyCode::Char('k') => self.counter_list.state.select_previous(),
-                KeyCode::Down | KeyCode::Char('j') => self.counter_list.state.select_next(),
-                KeyCode::Char(char) if char.is_numeric() => {
-                    input.handle_event(&Event::Key(key));
-                },
-                KeyCode::Right | KeyCode::Left | KeyCode::Backspace => {
-                    input.handle_event(&Event::Key(key));
-                }
-                KeyCode::Esc => self.input_mode = InputMode::Normal,
-                KeyCode::Enter => match self.counter_list.state.selected() {
-                    Some(index) => {
-                        match self.counter_list.counters.get_mut(index) {
-                            Some(counter) => {
-                                let value = u64::from_str(input.value()).expect("String should only have numerics");
-                                match sign {
-                                    AddingModeSign::Positive => counter.count += value as i64,
-                                    AddingModeSign::Negative => counter.count -= value as i64
-                                }
-                                input.reset();
-                                self.save()?;
-                            }
-                            None => {}
-                        }
-                    },
-                    None => {}
-                },
-                KeyCode::Char('a') => self.input_mode = InputMode::Adding(input.clone(), AddingModeSign::Positive),
-                KeyCode::Char('s') => self.input_mode = InputMode::Adding(input.clone(), AddingModeSign::Negative),
-                _ => {}
-            }
+        if changed {
+            self.dirty = true;
+            let selected = self.counter_list.selected_index();
+            self.counter_list.refresh_view(selected);
         }
-        Ok(())
     }
 
-    fn render_footer(&self, area: Rect, buf: &mut Buffer) {
-        let description = match &self.input_mode {
-            InputMode::Normal => {
-                if self.counter_list.counters.is_empty() {
-                    "Use n to make a new counter, and q to exit."
-                }
-                else {
-                    "Use ↓↑/jk to move, d to delete, ←→/l; to increment the counter, n to make a new counter, a/s to add/subtract, and q to exit."
-                }
-            }
-            InputMode::NewCounter(_) => "Type a new counter name. Use enter to add and esc to return.",
-            InputMode::Adding(_, sign) => match sign {
-                AddingModeSign::Positive => "Use ↓↑/jk to move, Type numbers, then enter to add and esc to return",
-                AddingModeSign::Negative => "Use ↓↑/jk to move, Type numbers, then enter to subtract and esc to return",
+    /// Counts the time since the last tick on running stopwatches and countdowns.
+    fn catch_up_timers(&mut self) {
+        let mut changed = false;
+        let mut finished = vec![];
+        for counter in &mut self.counter_list.counters {
+            let running = counter.timer_started.is_some();
+            changed |= counter.catch_up_timer();
+            if running && counter.countdown.is_some() && counter.timer_started.is_none() {
+                finished.push(counter.name.clone());
             }
+        }
+        // Ticks aren't saved, since the next session catches up the same seconds from when the
+        // timer was last caught up, so autosave doesn't rewrite the save every second.
+        if !finished.is_empty() {
+            self.bell = true;
+            self.notify(format!("Time's up for '{}'", finished.join("', '")));
+            self.dirty = true;
+        }
+        if changed {
+            let selected = self.counter_list.selected_index();
+            self.counter_list.refresh_view(selected);
+        }
+    }
+
+    /// Replaces a save with its most recent backup, first copying the save to `name.json.pre-restore`
+    /// so a mistaken restore can be undone. Returns the backup's path and, if there was a save to
+    /// keep, where it went.
+    pub(crate) fn restore_backup(input_name: &str) -> anyhow::Result<(PathBuf, Option<PathBuf>)> {
+        let path = Self::save_path(input_name)?;
+        let backup = backup_path(&path, 0);
+        if !backup.exists() {
+            anyhow::bail!("No backup at {}", backup.display());
+        }
+        let kept = if path.exists() {
+            let mut kept_path = path.clone().into_os_string();
+            kept_path.push(".pre-restore");
+            let kept_path = PathBuf::from(kept_path);
+            fs::copy(&path, &kept_path).context(format!("Failed to write file: {}", kept_path.display()))?;
+            Some(kept_path)
+        }
+        else {
+            None
         };
-        Paragraph::new(description).centered().render(area, buf);
+        fs::copy(&backup, &path).context(format!("Failed to write file: {}", path.display()))?;
+        Ok((backup, kept))
     }
 
-    fn render_list(&mut self, area: Rect, buf: &mut Buffer) {
-        let block = Block::new()
-            .title(Line::raw("Counters").centered())
-            .borders(Borders::all())
-            .border_set(symbols::border::ROUNDED);
+    pub(crate) fn save_exists(input_name: &str) -> bool {
+        Self::save_path(input_name).is_ok_and(|path| path.exists())
+    }
 
-        // Iterate through all elements in the `items` and stylize them.
-        let items: Vec<ListItem> = self
-            .counter_list
-            .counters
-            .iter()
-            .map(|counter| ListItem::from(counter))
-            .collect();
+    /// Moves a save that can't be opened out of the way, so a fresh one can be started in its
+    /// place. Returns where it was moved to.
+    pub(crate) fn set_aside(input_name: &str) -> anyhow::Result<PathBuf> {
+        let path = Self::save_path(input_name)?;
+        let mut broken_path = path.clone().into_os_string();
+        broken_path.push(".broken");
+        let broken_path = PathBuf::from(broken_path);
+        fs::rename(&path, &broken_path).context(format!("Failed to move file: {}", path.display()))?;
+        Ok(broken_path)
+    }
 
-        // Create a List from all list items and highlight the currently selected one
-        let list = List::new(items)
-            .block(block)
-            //.highlight_style(SELECTED_STYLE)
-            .highlight_symbol(">")
-            .highlight_spacing(HighlightSpacing::Always);
+    /// Opens a save that has to exist already, for the command line operations.
+    pub(crate) fn open_existing(input_name: &str) -> anyhow::Result<Self> {
+        let path = Self::save_path(input_name)?;
+        if !Path::exists(&path) {
+            anyhow::bail!("No save file at {}", path.display());
+        }
+        Ok(Self::new(SaveFile::read(&path)?, SaveState::Save(path), Config::load()))
+    }
 
-        // We need to disambiguate this trait method as both `Widget` and `StatefulWidget` share the
-        // same method name `render`.
-        StatefulWidget::render(list, area, buf, &mut self.counter_list.state);
+    /// Checks that the save can be written to, so a problem is found at startup rather than
+    /// on every save.
+    pub(crate) fn check_writable(&self) -> anyhow::Result<()> {
+        match &self.save_state {
+            SaveState::Save(path) if !self.readonly => probe_writable(path),
+            _ => Ok(()),
+        }
     }
 
-    fn render_input(&mut self, area: Rect, buf: &mut Buffer) {
-        match &self.input_mode {
-            InputMode::Normal => {}
-            InputMode::NewCounter(input) => {
-                let block = Block::new()
-                    .title(Line::raw("New Counter").centered())
-                    .borders(Borders::all())
-                    .border_set(symbols::border::ROUNDED);
+    /// Keeps the loaded counters but stops saving them, like a temporary session.
+    pub(crate) fn stop_saving(&mut self) {
+        self.save_state = SaveState::DoNotSave;
+    }
 
-                Paragraph::new(input.value())
-                    .centered()
-                    .block(block)
-                    .render(area, buf);
-            }
-            InputMode::Adding(input, sign) => {
-                let block = Block::new()
-                    .title(Line::raw(match sign {
-                        AddingModeSign::Positive => "Adding",
-                        AddingModeSign::Negative => "Subtracting"
-                    }).centered())
-                    .borders(Borders::all())
-                    .border_set(symbols::border::ROUNDED);
+    /// Opens an existing save to look at without changing it. Daily resets and running timers
+    /// aren't caught up either, so the counts are shown as saved.
+    pub(crate) fn open_readonly(input_name: &str) -> anyhow::Result<Self> {
+        let mut app = Self::open_existing(input_name)?;
+        app.readonly = true;
+        app.autosave = false;
+        Ok(app)
+    }
 
-                Paragraph::new(input.value())
-                    .centered()
-                    .block(block)
-                    .render(area, buf);
+    /// Sets the counter called `name` to `value`, creating it if needed, and saves. Returns the
+    /// new value as displayed.
+    pub(crate) fn set_counter(&mut self, name: &str, value: &str) -> anyhow::Result<String> {
+        if let SaveState::DoNotSave = self.save_state {
+            anyhow::bail!("Can't set a counter without a save file");
+        }
+        // An empty name would make the save invalid, so it couldn't be opened again.
+        let name = name.trim();
+        if name.is_empty() {
+            anyhow::bail!("The counter needs a name");
+        }
+
+        let index = match self.counter_list.counters.iter().position(|counter| counter.name == name) {
+            Some(index) => index,
+            None => {
+                self.counter_list.counters.push(Counter::new(name));
+                self.counter_list.counters.len() - 1
             }
+        };
+        let counter = &mut self.counter_list.counters[index];
+        if counter.locked {
+            anyhow::bail!("{} is locked", counter.name);
         }
+        let count = parse_amount(value, counter.precision).context(format!("Invalid value: {}", value))?;
+        counter.set_count(counter.clamp(count));
+        let value = format_amount(counter.count, counter.precision);
+
+        self.save()?;
+        Ok(value)
     }
-}
 
-impl Widget for &mut App {
-    fn render(self, area: Rect, buf: &mut Buffer) {
-        let [main_area, footer_area] =
-            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(area);
+    /// Merges the counters from another save file into this one and saves. Returns a summary of
+    /// what changed.
+    ///
+    /// A counter whose name is already taken has its count added to the existing counter's,
+    /// rescaled to the existing precision, and keeps the existing step, target, color and group.
+    /// With `replace`, the imported count replaces the existing one instead. Either way the count
+    /// is kept within the existing limits, and locked counters are left alone and named in the
+    /// result. Counters with new names are added at the end as they are.
+    ///
+    /// Files ending in `.csv` are read as `name,count` rows, like the CSV export writes. Rows that
+    /// can't be read are skipped and counted in the result.
+    pub(crate) fn import(&mut self, path: &Path, replace: bool) -> anyhow::Result<String> {
+        if let SaveState::DoNotSave = self.save_state {
+            anyhow::bail!("Can't import without a save file");
+        }
 
-        let [adding_area, list_area] =
-            Layout::vertical([Constraint::Length(3), Constraint::Fill(1)]).areas(main_area);
+        let (imported, skipped) = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("csv") => read_csv(path)?,
+            _ => (SaveFile::read(path)?.counters.into_owned(), 0),
+        };
+        let (mut merged, mut added) = (0, 0);
+        let mut locked = Vec::new();
+        for counter in imported {
+            match self.counter_list.counters.iter_mut().find(|existing| existing.name == counter.name) {
+                Some(existing) if existing.locked => locked.push(counter.name),
+                Some(existing) => {
+                    let count = rescale(counter.count, counter.precision, existing.precision);
+                    let count = if replace { count } else { existing.count.saturating_add(count) };
+                    existing.set_count(existing.clamp(count));
+                    merged += 1;
+                }
+                None => {
+                    self.counter_list.counters.push(counter);
+                    added += 1;
+                }
+            }
+        }
 
-        match self.input_mode {
+        self.save()?;
+        let verb = if replace { "Replaced" } else { "Merged" };
+        let mut summary = format!("{} {} and added {} counters from {}", verb, merged, added, path.display());
+        if skipped > 0 {
+            summary.push_str(&format!(", skipping {} rows that couldn't be read", skipped));
+        }
+        if !locked.is_empty() {
+            summary.push_str(&format!(", leaving locked {}", locked.join(", ")));
+        }
+        Ok(summary)
+    }
+
+    /// Every counter as JSON, in the same shape as in a save.
+    pub(crate) fn counters_json(&self) -> anyhow::Result<String> {
+        serde_json::to_string_pretty(&self.counter_list.counters).context("Failed to write counters")
+    }
+
+    /// Every counter as a `name: count` line.
+    pub(crate) fn list(&self) -> String {
+        self.counter_list
+            .counters
+            .iter()
+            .map(|counter| format!("{}: {}\n", counter.name, format_amount(counter.count, counter.precision)))
+            .collect()
+    }
+
+    fn save(&mut self) -> anyhow::Result<()> {
+        let SaveState::Save(buf) = &self.save_state else {
+            return Ok(());
+        };
+
+        // Backing up only before the first save of a session keeps the backups from filling up
+        // with the states in between autosaves.
+        if !self.backed_up {
+            rotate_backups(buf, self.config.backups)?;
+            self.backed_up = true;
+        }
+        SaveFile::new(&self.counter_list.counters, &self.counter_list.archived, self.counter_list.selected_index(), self.goal).write(buf)?;
+        // Autosaves come shortly after other changes, so they don't cover up what those said.
+        if self.message.is_none() {
+            self.notify("Saved".to_owned());
+        }
+        Ok(())
+    }
+
+    /// Leaves a message in the footer for a few seconds.
+    fn notify(&mut self, message: String) {
+        self.message = Some((message, Instant::now()));
+    }
+    
+    /// Applies a change to the counters and records it so it can be undone.
+    fn perform(&mut self, action: Action) {
+        let selected = self.counter_list.selected_index().and_then(|index| action.remap(index));
+        action.apply(&mut self.counter_list);
+        self.counter_list.refresh_view(selected);
+        self.undo_stack.push(action);
+        self.redo_stack.clear();
+        self.dirty = true;
+    }
+
+    /// The counters a reset of every counter would change: the ones not at zero that aren't
+    /// locked.
+    fn resettable(&self) -> Vec<(usize, i64)> {
+        self.counter_list
+            .counters
+            .iter()
+            .enumerate()
+            .filter(|(_, counter)| counter.count != 0 && !counter.locked)
+            .map(|(index, counter)| (index, counter.count))
+            .collect()
+    }
+
+    /// Zeroes every counter that isn't locked, as one change to undo.
+    fn reset_all(&mut self) {
+        let from = self.resettable();
+        if from.is_empty() {
+            self.notify("Nothing to reset".to_owned());
+            return;
+        }
+        self.perform(Action::ResetAll { from });
+    }
+
+    /// The counters clearing the list would delete: all but the locked ones.
+    fn clearable(&self) -> Vec<(usize, Counter)> {
+        self.counter_list
+            .counters
+            .iter()
+            .enumerate()
+            .filter(|(_, counter)| !counter.locked)
+            .map(|(index, counter)| (index, counter.clone()))
+            .collect()
+    }
+
+    /// Deletes every counter that isn't locked, as one change to undo.
+    fn clear(&mut self) {
+        let removed = self.clearable();
+        if removed.is_empty() {
+            self.notify("Nothing to delete".to_owned());
+            return;
+        }
+        self.perform(Action::Clear { removed });
+    }
+
+    /// The input adding and subtracting start with: the selected counter's step if
+    /// `prefill_adding` is on, and otherwise nothing.
+    fn adding_input(&self) -> Input {
+        match self.counter_list.selected_index() {
+            Some(index) if self.config.prefill_adding => {
+                let counter = &self.counter_list.counters[index];
+                Input::new(format_amount(counter.step, counter.precision))
+            }
+            _ => Input::default(),
+        }
+    }
+
+    /// Deletes a counter, keeping it to be brought back with U.
+    fn delete(&mut self, index: usize) {
+        let counter = self.counter_list.counters[index].clone();
+        self.notify(format!("Deleted '{}'. Use U to bring it back.", counter.name));
+        self.last_deleted = Some((index, counter.clone()));
+        self.perform(Action::Remove { index, counter });
+    }
+
+    /// Puts the last deleted counter back where it was, even after other changes that undo
+    /// would have to go back through first.
+    fn restore_deleted(&mut self) {
+        let Some((index, counter)) = self.last_deleted.take() else {
+            self.notify("Nothing deleted to bring back".to_owned());
+            return;
+        };
+        let index = index.min(self.counter_list.counters.len());
+        self.perform(Action::Insert { index, counter });
+        self.counter_list.refresh_view(Some(index));
+    }
+
+    /// Whether the counter at `index` can be changed, leaving a message in the footer if not.
+    fn check_unlocked(&mut self, index: usize) -> bool {
+        let counter = &self.counter_list.counters[index];
+        if counter.locked {
+            let message = format!("'{}' is locked. Use L to unlock it.", counter.name);
+            self.notify(message);
+            return false;
+        }
+        true
+    }
+
+    /// Adds the amount for a digit key to the selected counter: the one set in `quick_amounts`,
+    /// or the digit itself.
+    fn quick_add(&mut self, digit: char) {
+        let Some(index) = self.counter_list.selected_index() else {
+            return;
+        };
+        let counter = &self.counter_list.counters[index];
+        let amount = self.config.quick_amounts.get(&digit).cloned().unwrap_or_else(|| digit.to_string());
+        let Some(value) = evaluate(&amount, counter.precision) else {
+            self.notify(format!("Can't add '{}' for {}. Check quick_amounts in the config.", amount, digit));
+            return;
+        };
+        self.set_count(index, counter.count.saturating_add(value));
+    }
+
+    fn set_count(&mut self, index: usize, count: i64) {
+        if !self.check_unlocked(index) {
+            return;
+        }
+        let counter = &self.counter_list.counters[index];
+        let from = counter.count;
+        let clamped = counter.clamp(count);
+        if clamped != count {
+            let bound = if clamped < count { "maximum" } else { "minimum" };
+            let message = format!("'{}' is at its {} of {}.", counter.name, bound, format_amount(clamped, counter.precision));
+            self.notify(message);
+        }
+        let count = clamped;
+        if from != count {
+            self.perform(Action::SetCount { index, from, to: count });
+        }
+    }
+
+    fn undo(&mut self) {
+        if let Some(action) = self.undo_stack.pop() {
+            action.revert(&mut self.counter_list);
+            // Undoing the delete already brought the counter back.
+            if matches!(action, Action::Remove { .. }) {
+                self.last_deleted = None;
+            }
+            self.counter_list.refresh_view(action.index(true));
+            self.redo_stack.push(action);
+            self.dirty = true;
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(action) = self.redo_stack.pop() {
+            action.apply(&mut self.counter_list);
+            self.counter_list.refresh_view(action.index(false));
+            self.undo_stack.push(action);
+            self.dirty = true;
+        }
+    }
+
+    /// Swaps the selected counter with its neighbour in the list. Does nothing at the ends of the
+    /// list, or when a sort mode decides the order.
+    fn move_selected(&mut self, up: bool) {
+        if self.counter_list.sort != SortMode::Insertion {
+            return;
+        }
+        let Some(row) = self.counter_list.state.selected() else {
+            return;
+        };
+        let Some(from) = self.counter_list.counter_at(row) else {
+            return;
+        };
+        // Counters only move within their group, so a header above or below stops them. They
+        // also stay among the pinned or unpinned ones.
+        let neighbour = if up { row.checked_sub(1) } else { Some(row + 1) };
+        let Some(to) = neighbour.and_then(|neighbour| self.counter_list.counter_at(neighbour)) else {
+            return;
+        };
+        let counters = &self.counter_list.counters;
+        if counters[from].pinned != counters[to].pinned {
+            return;
+        }
+        self.perform(Action::Swap { from, to });
+    }
+
+    /// Adds or removes decimal places on the selected counter, keeping its value.
+    fn change_precision(&mut self, more: bool) {
+        let Some(index) = self.counter_list.selected_index().filter(|index| self.check_unlocked(*index)) else {
+            return;
+        };
+        let from = self.counter_list.counters[index].clone();
+        let precision = if more {
+            (from.precision + 1).min(MAX_PRECISION)
+        }
+        else {
+            from.precision.saturating_sub(1)
+        };
+        if precision != from.precision {
+            let to = from.with_precision(precision);
+            self.perform(Action::Replace { index, from: Box::new(from), to: Box::new(to) });
+        }
+    }
+
+    fn set_filter(&mut self, filter: &str) {
+        let selected = self.counter_list.selected_index();
+        self.counter_list.filter = filter.to_owned();
+        self.counter_list.refresh_view(selected);
+    }
+
+    fn cycle_sort(&mut self) {
+        let selected = self.counter_list.selected_index();
+        self.counter_list.sort = self.counter_list.sort.next();
+        self.counter_list.refresh_view(selected);
+    }
+
+    fn copy_selected(&mut self) -> anyhow::Result<()> {
+        let Some(index) = self.counter_list.selected_index() else {
+            return Ok(());
+        };
+        let counter = &self.counter_list.counters[index];
+        let value = format_amount(counter.count, counter.precision);
+
+        if self.clipboard.is_none() {
+            self.clipboard = Some(Clipboard::new().context("Couldn't open the clipboard")?);
+        }
+        if let Some(clipboard) = &mut self.clipboard {
+            clipboard.set_text(value.clone()).context("Couldn't copy to the clipboard")?;
+        }
+        self.notify(format!("Copied {}", value));
+        Ok(())
+    }
+
+    /// Saves if there are pending changes. A read-only save is never written.
+    fn flush(&mut self) -> anyhow::Result<()> {
+        if self.readonly {
+            return Ok(());
+        }
+        let selected = self.counter_list.selected_index();
+        if self.dirty || selected != self.saved_selection {
+            self.save()?;
+            self.dirty = false;
+            self.saved_selection = selected;
+        }
+        Ok(())
+    }
+
+    /// Where exports with the given extension are written: next to the save file, or in the
+    /// working directory for a temporary session.
+    fn export_path(&self, extension: &str) -> anyhow::Result<PathBuf> {
+        Ok(match &self.save_state {
+            SaveState::Save(path) => path.with_extension(extension),
+            SaveState::DoNotSave => {
+                let mut path = current_dir().context("Couldn't get working directory")?;
+                path.push("counters");
+                path.set_extension(extension);
+                path
+            }
+        })
+    }
+
+    /// Exports the counters to a file next to the save.
+    fn export(&mut self, format: ExportFormat) -> anyhow::Result<()> {
+        let path = self.export_path(format.extension())?;
+        match format {
+            ExportFormat::Csv => self.export_csv(&path)?,
+            ExportFormat::Markdown => self.export_markdown(&path)?,
+            ExportFormat::JsonLines => self.export_json_lines(&path)?,
+        }
+        self.notify(format!("Exported to {}", path.display()));
+        Ok(())
+    }
+
+    fn export_csv(&self, path: &Path) -> anyhow::Result<()> {
+        let file = File::create(path).context(format!("Failed to open file: {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+
+        writeln!(writer, "name,count").context(format!("Failed to write file: {}", path.display()))?;
+        for counter in &self.counter_list.counters {
+            writeln!(writer, "{},{}", csv_field(&counter.name), format_amount(counter.count, counter.precision)).context(format!("Failed to write file: {}", path.display()))?;
+        }
+        writer.flush().context(format!("Failed to write file: {}", path.display()))?;
+
+        Ok(())
+    }
+
+    fn export_markdown(&self, path: &Path) -> anyhow::Result<()> {
+        let file = File::create(path).context(format!("Failed to open file: {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+
+        writeln!(writer, "| Name | Count |").context(format!("Failed to write file: {}", path.display()))?;
+        writeln!(writer, "| --- | ---: |").context(format!("Failed to write file: {}", path.display()))?;
+        for counter in &self.counter_list.counters {
+            writeln!(writer, "| {} | {} |", markdown_cell(&counter.name), format_amount(counter.count, counter.precision)).context(format!("Failed to write file: {}", path.display()))?;
+        }
+        writer.flush().context(format!("Failed to write file: {}", path.display()))?;
+
+        Ok(())
+    }
+
+    fn export_json_lines(&self, path: &Path) -> anyhow::Result<()> {
+        let file = File::create(path).context(format!("Failed to open file: {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+
+        for counter in &self.counter_list.counters {
+            // Whole counts stay integers, so they come out exact.
+            let count = if counter.precision == 0 { json!(counter.count) } else { json!(counter.value()) };
+            // Written by hand to keep the name first, since `json!` sorts the keys.
+            writeln!(writer, "{{\"name\":{},\"count\":{}}}", json!(counter.name), count).context(format!("Failed to write file: {}", path.display()))?;
+        }
+        writer.flush().context(format!("Failed to write file: {}", path.display()))?;
+
+        Ok(())
+    }
+
+    /// Runs the TUI until it's quit, or until there's been no input for `idle_timeout`.
+    ///
+    /// Errors while it runs, like a save failing, are shown in the footer and don't stop it, so
+    /// the user can fix the problem and save again. Returns the error from the last save if it
+    /// failed when exiting after `idle_timeout`.
+    pub(crate) fn run(&mut self, mut terminal: Terminal<impl Backend + Write>, idle_timeout: Option<Duration>) -> io::Result<String> {
+        let mut last_input = Instant::now();
+
+        loop {
+            if self.should_exit {
+                match self.flush() {
+                    Ok(()) => break,
+                    // Rather than losing the changes, ask again, so the user can retry or
+                    // choose to exit without saving.
+                    Err(error) => {
+                        self.should_exit = false;
+                        self.input_mode = InputMode::ConfirmQuit;
+                        self.notify(format!("{:#}", error));
+                    }
+                }
+            }
+            if self.message.as_ref().is_some_and(|(_, left)| left.elapsed() >= MESSAGE_DURATION) {
+                self.message = None;
+            }
+            if !self.readonly {
+                self.reset_daily_counters();
+                self.catch_up_timers();
+            }
+            if self.bell {
+                self.bell = false;
+                terminal.backend_mut().write_all(b"\x07")?;
+            }
+            terminal.draw(|f| f.render_widget(&mut *self, f.size()))?;
+            if idle_timeout.is_some_and(|timeout| last_input.elapsed() >= timeout) {
+                return Ok(self.flush().err().map_or_else(String::new, |error| format!("{:#}", error)));
+            }
+            if event::poll(TICK)? {
+                let event = event::read()?;
+                if matches!(event, Event::Key(_) | Event::Mouse(_)) {
+                    last_input = Instant::now();
+                }
+                match event {
+                    Event::Key(key) => {
+                        if let Err(error) = self.handle_key(key) {
+                            self.notify(format!("{:#}", error));
+                        }
+                    }
+                    Event::Mouse(mouse) => self.handle_mouse(mouse),
+                    // Drawing resizes the terminal's buffers, so the loop only has to come back
+                    // around to lay everything out at the new size.
+                    Event::Resize(_, _) => {}
+                    _ => {}
+                }
+            }
+            // A failed autosave leaves the changes pending, so it's tried again on the next tick.
+            else if self.autosave && last_input.elapsed() >= SAVE_DELAY {
+                if let Err(error) = self.flush() {
+                    self.notify(format!("{:#}", error));
+                }
+            }
+        }
+        Ok(String::new())
+    }
+
+    /// Clicking a row selects it, and scrolling over a counter increments or decrements it.
+    fn handle_mouse(&mut self, mouse: MouseEvent) {
+        if !matches!(self.input_mode, InputMode::Normal) {
+            return;
+        }
+        let Some(row) = self.counter_list.row_at(mouse.column, mouse.row) else {
+            return;
+        };
+        let Some(index) = self.counter_list.counter_at(row) else {
+            return;
+        };
+        let counter = &self.counter_list.counters[index];
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => self.counter_list.state.select(Some(row)),
+            MouseEventKind::ScrollUp if !self.readonly => self.set_count(index, counter.count.saturating_add(counter.step)),
+            MouseEventKind::ScrollDown if !self.readonly => self.set_count(index, counter.count.saturating_sub(counter.step)),
+            _ => {}
+        }
+    }
+
+    fn quit(&mut self) {
+        // With autosave on, pending changes are flushed on the way out anyway.
+        if !self.autosave && self.dirty && matches!(self.save_state, SaveState::Save(_)) {
+            self.input_mode = InputMode::ConfirmQuit;
+        }
+        else {
+            self.should_exit = true;
+        }
+    }
+
+    /// Runs a line typed in command mode, like `rename foo` or `set 100`. Mistakes in the
+    /// command are shown in the footer.
+    fn run_command(&mut self, line: &str) -> anyhow::Result<()> {
+        let line = line.trim();
+        let (command, argument) = line.split_once(' ').map_or((line, ""), |(command, argument)| (command, argument.trim()));
+        let selected = self.counter_list.selected_index();
+        if self.readonly && !matches!(command, "" | "q" | "quit" | "tag") {
+            self.notify("The save is open read-only, so it can't be changed.".to_owned());
+            return Ok(());
+        }
+        match (command, selected) {
+            ("", _) => {}
+            ("q" | "quit", _) => self.quit(),
+            ("w" | "save", _) => self.flush()?,
+            ("u" | "undo", _) => self.undo(),
+            ("redo", _) => self.redo(),
+            ("clear", _) => self.input_mode = InputMode::ConfirmClear,
+            ("saveas", _) if argument.is_empty() => self.input_mode = InputMode::SaveAs(Input::default()),
+            ("saveas", _) => self.start_save_as(argument)?,
+            ("goal", _) if argument.is_empty() || argument == "none" => {
+                self.goal = None;
+                self.dirty = true;
+            }
+            ("goal", _) => {
+                // A goal below one would be met before anything was counted.
+                let Some(mut amount) = evaluate(argument, MAX_PRECISION).filter(|amount| *amount > 0) else {
+                    self.notify("Usage: goal NUMBER above zero, or goal none to remove it".to_owned());
+                    return Ok(());
+                };
+                // Only keep the decimals the goal was typed with.
+                let mut precision = MAX_PRECISION;
+                while precision > 0 && amount % 10 == 0 {
+                    amount /= 10;
+                    precision -= 1;
+                }
+                self.goal = Some(Goal { amount, precision });
+                self.dirty = true;
+            }
+            ("tag", _) => {
+                let tag = argument.trim_start_matches('#');
+                self.set_filter(&if tag.is_empty() { String::new() } else { format!("#{}", tag) });
+            }
+            ("countdown", Some(index)) => {
+                if !self.check_unlocked(index) {
+                    return Ok(());
+                }
+                let from = self.counter_list.counters[index].clone();
+                let mut to = from.clone();
+                match argument {
+                    "none" => {
+                        to.countdown = None;
+                        to.timer_started = None;
+                    }
+                    "reset" if from.countdown.is_some() => to.reset_countdown(),
+                    _ => {
+                        let Some(seconds) = parse_duration(argument) else {
+                            self.notify("Usage: countdown SECONDS or MM:SS, countdown reset or countdown none".to_owned());
+                            return Ok(());
+                        };
+                        to.countdown = Some(seconds);
+                        to.display_format = DisplayFormat::Duration;
+                        to.reset_countdown();
+                    }
+                }
+                self.perform(Action::Replace { index, from: Box::new(from), to: Box::new(to) });
+            }
+            ("export", _) => match ExportFormat::from_name(argument) {
+                Some(format) => self.export(format)?,
+                None => self.notify(format!("Can't export as '{}'. Try export csv, export md or export jsonl.", argument)),
+            },
+            ("new", _) => {
+                let (name, initial) = split_initial_value(argument);
+                if name.is_empty() {
+                    self.notify("Usage: new NAME or new NAME=VALUE".to_owned());
+                    return Ok(());
+                }
+                let Some(name) = self.counter_list.resolve_name(name, None, self.config.duplicate_names) else {
+                    self.notify(format!("A counter named '{}' already exists.", name));
+                    return Ok(());
+                };
+                let mut counter = Counter::new(&name);
+                if let Some((count, precision)) = initial {
+                    counter.count = count;
+                    counter.precision = precision;
+                }
+                self.perform(Action::Insert { index: self.counter_list.counters.len(), counter });
+            }
+            ("rename" | "set" | "add" | "sub" | "delete" | "move" | "countdown" | "weight", None) => self.notify(format!("Select a counter to {} first.", command)),
+            ("rename", Some(index)) => {
+                if argument.is_empty() {
+                    self.notify("Usage: rename NAME".to_owned());
+                    return Ok(());
+                }
+                let Some(to) = self.counter_list.resolve_name(argument, Some(index), self.config.duplicate_names) else {
+                    self.notify(format!("A counter named '{}' already exists.", argument));
+                    return Ok(());
+                };
+                let from = self.counter_list.counters[index].name.clone();
+                self.perform(Action::Rename { index, from, to });
+            }
+            ("set" | "add" | "sub", Some(index)) => {
+                let counter = &self.counter_list.counters[index];
+                let Some(value) = evaluate(argument, counter.precision) else {
+                    self.notify(format!("Usage: {} NUMBER", command));
+                    return Ok(());
+                };
+                let count = match command {
+                    "add" => counter.count.saturating_add(value),
+                    "sub" => counter.count.saturating_sub(value),
+                    _ => value,
+                };
+                self.set_count(index, count);
+            }
+            ("delete", Some(index)) => {
+                if self.check_unlocked(index) {
+                    self.delete(index);
+                }
+            }
+            ("weight", Some(index)) => {
+                let Some(weight) = argument.parse::<f64>().ok().filter(|weight| weight.is_finite()) else {
+                    self.notify("Usage: weight NUMBER, like weight 3 or weight 0.5".to_owned());
+                    return Ok(());
+                };
+                let from = self.counter_list.counters[index].clone();
+                let to = Counter { weight, ..from.clone() };
+                self.perform(Action::Replace { index, from: Box::new(from), to: Box::new(to) });
+            }
+            ("move", Some(index)) => {
+                if argument.is_empty() {
+                    self.notify("Usage: move SAVE".to_owned());
+                    return Ok(());
+                }
+                if self.check_unlocked(index) {
+                    if let Err(error) = self.move_to_file(index, argument) {
+                        self.notify(format!("{:#}", error));
+                    }
+                }
+            }
+            _ => self.notify(format!("Unknown command: {}", command)),
+        }
+        Ok(())
+    }
+
+    /// How many steps an increment or decrement moves: more the longer its key is held, so large
+    /// values can be reached by holding it.
+    fn held_multiplier(&mut self, increment: bool) -> i64 {
+        let now = Instant::now();
+        let presses = match &self.held {
+            Some(held) if held.increment == increment && now - held.last_press < HOLD_WINDOW => held.presses + 1,
+            _ => 1,
+        };
+        self.held = Some(Held { increment, last_press: now, presses });
+        match presses {
+            0..=10 => 1,
+            11..=30 => 5,
+            _ => 10,
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> anyhow::Result<()> {
+        if key.kind != KeyEventKind::Press {
+            return Ok(());
+        }
+        if !(self.config.keys.increment.matches(&key) || self.config.keys.decrement.matches(&key)) {
+            self.held = None;
+        }
+        self.message = None;
+
+        // Any key ends a sequence, but only the right one completes it.
+        if let (Some((pending, started)), InputMode::Normal) = (self.pending.take(), &self.input_mode) {
+            if started.elapsed() < SEQUENCE_TIMEOUT {
+                match pending {
+                    Pending::Top if key.code == KeyCode::Char('g') => {
+                        self.counter_list.select_first();
+                        return Ok(());
+                    }
+                    Pending::Delete if self.config.keys.delete.matches(&key) => {
+                        if let Some(index) = self.counter_list.selected_index().filter(|index| self.check_unlocked(*index)) {
+                            self.delete(index);
+                        }
+                        return Ok(());
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if self.readonly && matches!(self.input_mode, InputMode::Normal) && !self.allowed_when_readonly(&key) {
+            self.notify("The save is open read-only, so it can't be changed.".to_owned());
+            return Ok(());
+        }
+
+        match &mut self.input_mode {
+            InputMode::Normal => match key.code {
+                _ if self.config.keys.increment.matches(&key) => {
+                    if let Some(index) = self.counter_list.selected_index() {
+                        let multiplier = self.held_multiplier(true);
+                        let counter = &self.counter_list.counters[index];
+                        self.set_count(index, counter.count.saturating_add(counter.step.saturating_mul(multiplier)));
+                    }
+                },
+                _ if self.config.keys.decrement.matches(&key) => {
+                    if let Some(index) = self.counter_list.selected_index() {
+                        let multiplier = self.held_multiplier(false);
+                        let counter = &self.counter_list.counters[index];
+                        self.set_count(index, counter.count.saturating_sub(counter.step.saturating_mul(multiplier)));
+                    }
+                },
+                _ if self.config.keys.quit.matches(&key) => self.quit(),
+                _ if self.config.keys.new.matches(&key) => self.input_mode = InputMode::NewCounter(Input::default()),
+                _ if self.config.keys.delete.matches(&key) && self.config.vim_delete => {
+                    self.pending = Some((Pending::Delete, Instant::now()));
+                },
+                _ if self.config.keys.delete.matches(&key) => {
+                    if let Some(index) = self.counter_list.selected_index().filter(|index| self.check_unlocked(*index)) {
+                        self.input_mode = InputMode::ConfirmDelete(index);
+                    }
+                },
+                _ if self.config.keys.add.matches(&key) => self.input_mode = InputMode::Adding(self.adding_input(), AddingModeSign::Positive),
+                _ if self.config.keys.subtract.matches(&key) => self.input_mode = InputMode::Adding(self.adding_input(), AddingModeSign::Negative),
+                KeyCode::Char('m') => self.input_mode = InputMode::Multiply(Input::default(), MultiplyModeOperation::Multiply),
+                KeyCode::Char('=') => self.input_mode = InputMode::SetValue(Input::default()),
+                KeyCode::Char('i') => {
+                    if let Some(index) = self.counter_list.selected_index().filter(|index| self.check_unlocked(*index)) {
+                        let counter = &self.counter_list.counters[index];
+                        let value = Input::new(format_amount(counter.count, counter.precision));
+                        // The chart has no rows to edit in, so the value is edited above it.
+                        self.input_mode = if self.show_chart { InputMode::SetValue(value) } else { InputMode::EditInline(value, index) };
+                    }
+                },
+                KeyCode::Right | KeyCode::Left if key.modifiers.contains(KeyModifiers::SHIFT) => {
+                    if let Some(index) = self.counter_list.selected_index() {
+                        let counter = &self.counter_list.counters[index];
+                        let change = counter.step.saturating_mul(BIG_STEPS);
+                        let count = if key.code == KeyCode::Right {
+                            counter.count.saturating_add(change)
+                        }
+                        else {
+                            counter.count.saturating_sub(change)
+                        };
+                        self.set_count(index, count);
+                    }
+                },
+                KeyCode::Up if key.modifiers.contains(KeyModifiers::SHIFT) => self.move_selected(true),
+                KeyCode::Down if key.modifiers.contains(KeyModifiers::SHIFT) => self.move_selected(false),
+                KeyCode::Char('K') => self.move_selected(true),
+                KeyCode::Char('J') => self.move_selected(false),
+                KeyCode::Up | KeyCode::Char('k') => self.counter_list.select_previous(),
+                KeyCode::Down | KeyCode::Char('j') => self.counter_list.select_next(),
+                KeyCode::PageUp => self.counter_list.select_page(false),
+                KeyCode::PageDown => self.counter_list.select_page(true),
+                KeyCode::Home => self.counter_list.select_first(),
+                KeyCode::End => self.counter_list.select_last(),
+                KeyCode::Char('g') => self.pending = Some((Pending::Top, Instant::now())),
+                KeyCode::Char('G') => self.counter_list.select_last(),
+                KeyCode::Char('0') => {
+                    if let Some(index) = self.counter_list.selected_index() {
+                        self.set_count(index, 0);
+                    }
+                },
+                KeyCode::Char(digit @ '1'..='9') if self.config.quick_add => self.quick_add(digit),
+                KeyCode::Char('R') => self.input_mode = InputMode::ConfirmReset,
+                KeyCode::Char('u') => self.undo(),
+                KeyCode::Char('U') => self.restore_deleted(),
+                KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => self.redo(),
+                KeyCode::Char('r') => {
+                    if let Some(index) = self.counter_list.selected_index() {
+                        if let Some(counter) = self.counter_list.counters.get(index) {
+                            self.input_mode = InputMode::Rename(Input::new(counter.name.clone()), index);
+                        }
+                    }
+                },
+                KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    self.theme = Theme::new(self.theme.name.next(), &self.config.colors);
+                },
+                KeyCode::Char('t') => {
+                    if let Some(index) = self.counter_list.selected_index() {
+                        if let Some(counter) = self.counter_list.counters.get(index) {
+                            self.input_mode = InputMode::EditStep(Input::new(format_amount(counter.step, counter.precision)), index);
+                        }
+                    }
+                },
+                KeyCode::Char('T') => {
+                    if let Some(index) = self.counter_list.selected_index() {
+                        let counter = &self.counter_list.counters[index];
+                        let target = counter.target.map(|target| format_amount(target, counter.precision)).unwrap_or_default();
+                        self.input_mode = InputMode::SetTarget(Input::new(target), index);
+                    }
+                },
+                KeyCode::Char('[') | KeyCode::Char(']') => {
+                    // The count is clamped to new limits, so a locked counter keeps its limits.
+                    if let Some(index) = self.counter_list.selected_index().filter(|index| self.check_unlocked(*index)) {
+                        let limit = if key.code == KeyCode::Char('[') { Limit::Min } else { Limit::Max };
+                        let counter = &self.counter_list.counters[index];
+                        let value = match limit {
+                            Limit::Min => counter.min,
+                            Limit::Max => counter.max,
+                        };
+                        let value = value.map(|value| format_amount(value, counter.precision)).unwrap_or_default();
+                        self.input_mode = InputMode::SetLimit(Input::new(value), index, limit);
+                    }
+                },
+                KeyCode::Char('y') => self.copy_selected()?,
+                KeyCode::Char('c') => {
+                    if let Some(index) = self.counter_list.selected_index() {
+                        let original = &self.counter_list.counters[index];
+                        let mut name = format!("{} (copy)", original.name);
+                        if !matches!(self.config.duplicate_names, DuplicateNames::Allow) {
+                            name = self.counter_list.unique_name(&name, None);
+                        }
+                        let counter = Counter { name, ..original.clone() };
+                        self.perform(Action::Insert { index: index + 1, counter });
+                        self.counter_list.refresh_view(Some(index + 1));
+                    }
+                },
+                KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    self.input_mode = InputMode::SaveAs(Input::default());
+                },
+                KeyCode::Char('w') => self.flush()?,
+                KeyCode::Char('W') => self.autosave = !self.autosave,
+                KeyCode::Char('D') => {
+                    if let Some(index) = self.counter_list.selected_index() {
+                        self.counter_list.counters[index].toggle_reset_daily();
+                        self.dirty = true;
+                    }
+                },
+                KeyCode::Char('P') => {
+                    if let Some(index) = self.counter_list.selected_index() {
+                        let counter = &mut self.counter_list.counters[index];
+                        counter.pinned = !counter.pinned;
+                        self.dirty = true;
+                        self.counter_list.refresh_view(Some(index));
+                    }
+                },
+                KeyCode::Char('L') => {
+                    if let Some(index) = self.counter_list.selected_index() {
+                        let counter = &mut self.counter_list.counters[index];
+                        counter.locked = !counter.locked;
+                        self.dirty = true;
+                    }
+                },
+                KeyCode::Char('p') => {
+                    if let Some(index) = self.counter_list.selected_index() {
+                        if self.check_unlocked(index) {
+                            self.counter_list.counters[index].toggle_timer();
+                            self.dirty = true;
+                        }
+                    }
+                },
+                KeyCode::Char('C') => {
+                    if let Some(index) = self.counter_list.selected_index() {
+                        self.counter_list.counters[index].cycle_color();
+                        self.dirty = true;
+                    }
+                },
+                KeyCode::Char('F') => {
+                    if let Some(index) = self.counter_list.selected_index() {
+                        let counter = &mut self.counter_list.counters[index];
+                        counter.display_format = counter.display_format.next();
+                        let message = format!("Showing '{}' as {}", counter.name, counter.display_format.name());
+                        self.notify(message);
+                        self.dirty = true;
+                    }
+                },
+                KeyCode::Char('b') => {
+                    if let Some(index) = self.counter_list.selected_index() {
+                        let group = self.counter_list.counters[index].group.clone().unwrap_or_default();
+                        self.input_mode = InputMode::SetGroup(Input::new(group), index);
+                    }
+                },
+                KeyCode::Char('N') => {
+                    if let Some(index) = self.counter_list.selected_index() {
+                        let note = self.counter_list.counters[index].note.clone().unwrap_or_default();
+                        self.input_mode = InputMode::EditNote(Input::new(note), index);
+                    }
+                },
+                KeyCode::Char('#') => {
+                    if let Some(index) = self.counter_list.selected_index() {
+                        let tags = self.counter_list.counters[index].tags.join(", ");
+                        self.input_mode = InputMode::EditTags(Input::new(tags), index);
+                    }
+                },
+                KeyCode::Char('.') => self.change_precision(true),
+                KeyCode::Char(',') => self.change_precision(false),
+                KeyCode::Char('o') => self.cycle_sort(),
+                KeyCode::Char('%') => self.show_shares = !self.show_shares,
+                KeyCode::Char('H') => self.show_clock = !self.show_clock,
+                KeyCode::Char('B') => self.show_chart = !self.show_chart,
+                KeyCode::Char('f') => self.input_mode = InputMode::Jump,
+                KeyCode::Char('e') => self.export(ExportFormat::Csv)?,
+                KeyCode::Char('E') => self.export(ExportFormat::Markdown)?,
+                KeyCode::Char('?') => self.input_mode = InputMode::Help(0),
+                KeyCode::Char(':') => self.input_mode = InputMode::Command(Input::default()),
+                KeyCode::Char('S') => self.input_mode = InputMode::Stats,
+                KeyCode::Char('x') => {
+                    if let Some(index) = self.counter_list.selected_index().filter(|index| self.check_unlocked(*index)) {
+                        let counter = self.counter_list.counters[index].clone();
+                        self.perform(Action::Archive { index, counter });
+                    }
+                },
+                KeyCode::Char('O') => match Self::save_names() {
+                    Ok(names) => {
+                        let current = match &self.save_state {
+                            SaveState::Save(path) => path.file_name().and_then(|name| name.to_str()),
+                            SaveState::DoNotSave => None,
+                        };
+                        let selected = names.iter().position(|name| Some(name.as_str()) == current).or((!names.is_empty()).then_some(0));
+                        self.input_mode = InputMode::OpenFile(names, ListState::default().with_selected(selected));
+                    }
+                    Err(error) => self.notify(format!("{:#}", error)),
+                },
+                KeyCode::Char('X') => {
+                    let selected = (!self.counter_list.archived.is_empty()).then_some(0);
+                    self.input_mode = InputMode::Archive(ListState::default().with_selected(selected));
+                },
+                KeyCode::Enter => {
+                    if let Some(index) = self.counter_list.selected_index() {
+                        self.input_mode = InputMode::Detail(index);
+                    }
+                },
+                KeyCode::Char('/') => self.input_mode = InputMode::Filter(Input::new(self.counter_list.filter.clone())),
+                KeyCode::Esc if !self.counter_list.filter.is_empty() => self.set_filter(""),
+                KeyCode::Esc => self.counter_list.state.select(None),
+                _ => {}
+            },
+            InputMode::NewCounter(input) => match key.code {
+                KeyCode::Esc => self.input_mode = InputMode::Normal,
+                KeyCode::Char('v') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    paste(input, &mut self.clipboard, |char| !char.is_control());
+                }
+                KeyCode::Enter => {
+                    let (name, initial) = split_initial_value(input.value());
+                    if name.is_empty() {
+                        return Ok(());
+                    }
+                    // A rejected name stays in the input, with a warning in the footer.
+                    let Some(name) = self.counter_list.resolve_name(name, None, self.config.duplicate_names) else {
+                        return Ok(());
+                    };
+                    let mut counter = Counter::new(&name);
+                    if let Some((count, precision)) = initial {
+                        counter.count = count;
+                        counter.precision = precision;
+                    }
+                    input.reset();
+                    self.perform(Action::Insert { index: self.counter_list.counters.len(), counter });
+                }
+                _ => {
+                    input.handle_event(&Event::Key(key));
+                }
+            },
+            InputMode::Adding(input, sign) => match key.code {
+                KeyCode::Up | KeyCode::Char('k') => self.counter_list.select_previous(),
+                KeyCode::Down | KeyCode::Char('j') => self.counter_list.select_next(),
+                KeyCode::PageUp => self.counter_list.select_page(false),
+                KeyCode::PageDown => self.counter_list.select_page(true),
+                KeyCode::Home => self.counter_list.select_first(),
+                KeyCode::End => self.counter_list.select_last(),
+                KeyCode::Char('p') => paste(input, &mut self.clipboard, |char| is_amount_char(char, &self.config)),
+                KeyCode::Char('v') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    paste(input, &mut self.clipboard, |char| is_amount_char(char, &self.config));
+                }
+                KeyCode::Char(char) if is_amount_char(char, &self.config) => {
+                    input.handle_event(&Event::Key(key));
+                },
+                KeyCode::Right | KeyCode::Left | KeyCode::Backspace => {
+                    input.handle_event(&Event::Key(key));
+                }
+                KeyCode::Esc => self.input_mode = InputMode::Normal,
+                KeyCode::Enter => {
+                    let Some(index) = self.counter_list.selected_index() else {
+                        return Ok(());
+                    };
+                    let counter = &self.counter_list.counters[index];
+                    // An empty or invalid expression is ignored so the user can keep typing.
+                    let Some(value) = evaluate(&amount_text(input.value(), &self.config), counter.precision) else {
+                        return Ok(());
+                    };
+                    let count = match sign {
+                        AddingModeSign::Positive => counter.count.saturating_add(value),
+                        AddingModeSign::Negative => counter.count.saturating_sub(value)
+                    };
+                    // When prefilled, the amount stays so enter can apply it again.
+                    if !self.config.prefill_adding {
+                        input.reset();
+                    }
+                    self.set_count(index, count);
+                },
+                _ if self.config.keys.add.matches(&key) => self.input_mode = InputMode::Adding(input.clone(), AddingModeSign::Positive),
+                _ if self.config.keys.subtract.matches(&key) => self.input_mode = InputMode::Adding(input.clone(), AddingModeSign::Negative),
+                _ => {}
+            },
+            InputMode::Multiply(input, operation) => match key.code {
+                KeyCode::Up | KeyCode::Char('k') => self.counter_list.select_previous(),
+                KeyCode::Down | KeyCode::Char('j') => self.counter_list.select_next(),
+                KeyCode::Char('*') => *operation = MultiplyModeOperation::Multiply,
+                KeyCode::Char('/') => *operation = MultiplyModeOperation::Divide,
+                KeyCode::Char(char) if char.is_ascii_digit() || char == '.' => {
+                    input.handle_event(&Event::Key(key));
+                },
+                KeyCode::Right | KeyCode::Left | KeyCode::Backspace => {
+                    input.handle_event(&Event::Key(key));
+                }
+                KeyCode::Esc => self.input_mode = InputMode::Normal,
+                KeyCode::Enter => {
+                    let Some(index) = self.counter_list.selected_index() else {
+                        return Ok(());
+                    };
+                    let counter = &self.counter_list.counters[index];
+                    let divide = matches!(operation, MultiplyModeOperation::Divide);
+                    // Invalid factors and division by zero are ignored so the user can keep typing.
+                    let Some(count) = parse_amount(input.value(), counter.precision)
+                        .and_then(|factor| scale_amount(counter.count, factor, counter.precision, divide)) else {
+                        return Ok(());
+                    };
+                    input.reset();
+                    self.set_count(index, count);
+                },
+                _ => {}
+            },
+            InputMode::SetValue(input) => match key.code {
+                KeyCode::Up | KeyCode::Char('k') => self.counter_list.select_previous(),
+                KeyCode::Down | KeyCode::Char('j') => self.counter_list.select_next(),
+                KeyCode::Char(char) if char.is_ascii_digit() || char == '.' || char == '-' => {
+                    input.handle_event(&Event::Key(key));
+                },
+                KeyCode::Right | KeyCode::Left | KeyCode::Backspace => {
+                    input.handle_event(&Event::Key(key));
+                }
+                KeyCode::Esc => self.input_mode = InputMode::Normal,
+                KeyCode::Enter => {
+                    let Some(index) = self.counter_list.selected_index() else {
+                        return Ok(());
+                    };
+                    let Some(count) = parse_amount(input.value(), self.counter_list.counters[index].precision) else {
+                        return Ok(());
+                    };
+                    self.input_mode = InputMode::Normal;
+                    self.set_count(index, count);
+                },
+                _ => {}
+            },
+            InputMode::EditInline(input, index) => match key.code {
+                KeyCode::Char(char) if char.is_ascii_digit() || char == '.' || char == '-' => {
+                    input.handle_event(&Event::Key(key));
+                },
+                KeyCode::Right | KeyCode::Left | KeyCode::Backspace | KeyCode::Delete | KeyCode::Home | KeyCode::End => {
+                    input.handle_event(&Event::Key(key));
+                }
+                KeyCode::Esc => self.input_mode = InputMode::Normal,
+                KeyCode::Enter => {
+                    let index = *index;
+                    // An invalid value stays in the row so it can be fixed.
+                    let Some(count) = parse_amount(input.value(), self.counter_list.counters[index].precision) else {
+                        return Ok(());
+                    };
+                    self.input_mode = InputMode::Normal;
+                    self.set_count(index, count);
+                },
+                _ => {}
+            },
+            InputMode::Rename(input, index) => match key.code {
+                KeyCode::Esc => self.input_mode = InputMode::Normal,
+                KeyCode::Enter => {
+                    let name = input.value().trim();
+                    if name.is_empty() {
+                        return Ok(());
+                    }
+                    let index = *index;
+                    let Some(to) = self.counter_list.resolve_name(name, Some(index), self.config.duplicate_names) else {
+                        return Ok(());
+                    };
+                    if let Some(counter) = self.counter_list.counters.get(index) {
+                        let from = counter.name.clone();
+                        self.perform(Action::Rename { index, from, to });
+                    }
+                    self.input_mode = InputMode::Normal;
+                }
+                _ => {
+                    input.handle_event(&Event::Key(key));
+                }
+            },
+            InputMode::SaveAs(input) => match key.code {
+                KeyCode::Esc => self.input_mode = InputMode::Normal,
+                KeyCode::Enter => {
+                    let name = input.value().trim().to_owned();
+                    if !name.is_empty() {
+                        self.input_mode = InputMode::Normal;
+                        self.start_save_as(&name)?;
+                    }
+                }
+                _ => {
+                    input.handle_event(&Event::Key(key));
+                }
+            },
+            InputMode::ConfirmOverwrite(path) => match key.code {
+                KeyCode::Char('y') => {
+                    let path = path.clone();
+                    self.save_as(path)?;
+                }
+                KeyCode::Char('n') | KeyCode::Esc => self.input_mode = InputMode::Normal,
+                _ => {}
+            },
+            InputMode::EditStep(input, index) => match key.code {
+                KeyCode::Char(char) if char.is_ascii_digit() || char == '.' => {
+                    input.handle_event(&Event::Key(key));
+                },
+                KeyCode::Right | KeyCode::Left | KeyCode::Backspace => {
+                    input.handle_event(&Event::Key(key));
+                }
+                KeyCode::Esc => self.input_mode = InputMode::Normal,
+                KeyCode::Enter => {
+                    let Some(counter) = self.counter_list.counters.get_mut(*index) else {
+                        return Ok(());
+                    };
+                    // A step of zero would make the counter impossible to move, so it's ignored.
+                    let Some(step) = parse_amount(input.value(), counter.precision).filter(|step| *step > 0) else {
+                        return Ok(());
+                    };
+                    counter.step = step;
+                    self.input_mode = InputMode::Normal;
+                    self.dirty = true;
+                }
+                _ => {}
+            },
+            InputMode::SetTarget(input, index) => match key.code {
+                KeyCode::Char(char) if char.is_ascii_digit() || char == '.' => {
+                    input.handle_event(&Event::Key(key));
+                },
+                KeyCode::Right | KeyCode::Left | KeyCode::Backspace => {
+                    input.handle_event(&Event::Key(key));
+                }
+                KeyCode::Esc => self.input_mode = InputMode::Normal,
+                KeyCode::Enter => {
+                    let Some(counter) = self.counter_list.counters.get_mut(*index) else {
+                        return Ok(());
+                    };
+                    // An empty input clears the target.
+                    if input.value().is_empty() {
+                        counter.target = None;
+                    }
+                    else {
+                        let Some(target) = parse_amount(input.value(), counter.precision) else {
+                            return Ok(());
+                        };
+                        counter.target = Some(target);
+                    }
+                    self.input_mode = InputMode::Normal;
+                    self.dirty = true;
+                }
+                _ => {}
+            },
+            InputMode::SetLimit(input, index, limit) => match key.code {
+                KeyCode::Char(char) if char.is_ascii_digit() || char == '.' || char == '-' => {
+                    input.handle_event(&Event::Key(key));
+                },
+                KeyCode::Right | KeyCode::Left | KeyCode::Backspace => {
+                    input.handle_event(&Event::Key(key));
+                }
+                KeyCode::Esc => self.input_mode = InputMode::Normal,
+                KeyCode::Enter => {
+                    let Some(counter) = self.counter_list.counters.get(*index) else {
+                        return Ok(());
+                    };
+                    // An empty input clears the limit.
+                    let value = if input.value().is_empty() {
+                        None
+                    }
+                    else {
+                        let Some(value) = parse_amount(input.value(), counter.precision) else {
+                            return Ok(());
+                        };
+                        Some(value)
+                    };
+                    let (min, max) = match limit {
+                        Limit::Min => (value, counter.max),
+                        Limit::Max => (counter.min, value),
+                    };
+                    // A minimum above the maximum would leave no valid count.
+                    if let (Some(min), Some(max)) = (min, max) {
+                        if min > max {
+                            return Ok(());
+                        }
+                    }
+                    // The count is brought within the new limits in the same change, so undoing it
+                    // restores both together.
+                    let from = counter.clone();
+                    let mut to = Counter { min, max, ..from.clone() };
+                    let count = to.clamp(to.count);
+                    if count != to.count {
+                        to.set_count(count);
+                    }
+                    let index = *index;
+                    self.input_mode = InputMode::Normal;
+                    self.perform(Action::Replace { index, from: Box::new(from), to: Box::new(to) });
+                }
+                _ => {}
+            },
+            InputMode::SetGroup(input, index) => match key.code {
+                KeyCode::Esc => self.input_mode = InputMode::Normal,
+                KeyCode::Enter => {
+                    let index = *index;
+                    // An empty input takes the counter out of its group.
+                    let group = Some(input.value().trim()).filter(|group| !group.is_empty()).map(str::to_owned);
+                    if let Some(counter) = self.counter_list.counters.get_mut(index) {
+                        counter.group = group;
+                        self.dirty = true;
+                    }
+                    self.input_mode = InputMode::Normal;
+                    self.counter_list.refresh_view(Some(index));
+                }
+                _ => {
+                    input.handle_event(&Event::Key(key));
+                }
+            },
+            InputMode::EditNote(input, index) => match key.code {
+                KeyCode::Esc => self.input_mode = InputMode::Normal,
+                KeyCode::Enter => {
+                    // An empty input removes the note.
+                    let note = Some(input.value().trim()).filter(|note| !note.is_empty()).map(str::to_owned);
+                    if let Some(counter) = self.counter_list.counters.get_mut(*index) {
+                        counter.note = note;
+                        self.dirty = true;
+                    }
+                    self.input_mode = InputMode::Normal;
+                }
+                _ => {
+                    input.handle_event(&Event::Key(key));
+                }
+            },
+            InputMode::EditTags(input, index) => match key.code {
+                KeyCode::Esc => self.input_mode = InputMode::Normal,
+                KeyCode::Enter => {
+                    let mut tags: Vec<String> = Vec::new();
+                    for tag in input.value().split(',') {
+                        // A leading # is allowed, the way tags are filtered by.
+                        let tag = tag.trim().trim_start_matches('#').trim();
+                        if !tag.is_empty() && !tags.iter().any(|own| own.eq_ignore_ascii_case(tag)) {
+                            tags.push(tag.to_owned());
+                        }
+                    }
+                    let index = *index;
+                    if let Some(counter) = self.counter_list.counters.get_mut(index) {
+                        counter.tags = tags;
+                        self.dirty = true;
+                    }
+                    self.input_mode = InputMode::Normal;
+                    // The counter may have fallen in or out of a tag filter.
+                    self.counter_list.refresh_view(Some(index));
+                }
+                _ => {
+                    input.handle_event(&Event::Key(key));
+                }
+            },
+            InputMode::ConfirmDelete(index) => match key.code {
+                KeyCode::Char('y') => {
+                    let index = *index;
+                    if index < self.counter_list.counters.len() {
+                        self.delete(index);
+                    }
+                    self.input_mode = InputMode::Normal;
+                }
+                KeyCode::Char('n') | KeyCode::Esc => self.input_mode = InputMode::Normal,
+                _ => {}
+            },
+            InputMode::ConfirmClear => match key.code {
+                KeyCode::Char('y') => {
+                    self.input_mode = InputMode::Normal;
+                    self.clear();
+                }
+                KeyCode::Char('n') | KeyCode::Esc => self.input_mode = InputMode::Normal,
+                _ => {}
+            },
+            InputMode::ConfirmReset => match key.code {
+                KeyCode::Char('y') => {
+                    self.input_mode = InputMode::Normal;
+                    self.reset_all();
+                }
+                KeyCode::Char('n') | KeyCode::Esc => self.input_mode = InputMode::Normal,
+                _ => {}
+            },
+            InputMode::Command(input) => match key.code {
+                KeyCode::Esc => self.input_mode = InputMode::Normal,
+                KeyCode::Enter => {
+                    let line = input.value().to_owned();
+                    self.input_mode = InputMode::Normal;
+                    self.run_command(&line)?;
+                }
+                _ => {
+                    input.handle_event(&Event::Key(key));
+                }
+            },
+            InputMode::Jump => {
+                if let KeyCode::Char(letter) = key.code {
+                    self.counter_list.select_starting_with(letter);
+                }
+                self.input_mode = InputMode::Normal;
+            }
+            // Scrolling past the end is caught when drawing, which knows how much fits.
+            InputMode::Help(offset) => match key.code {
+                KeyCode::Down | KeyCode::Char('j') => *offset += 1,
+                KeyCode::Up | KeyCode::Char('k') => *offset = offset.saturating_sub(1),
+                KeyCode::PageDown => *offset += self.help_page.max(1),
+                KeyCode::PageUp => *offset = offset.saturating_sub(self.help_page.max(1)),
+                _ => self.input_mode = InputMode::Normal,
+            },
+            InputMode::Stats => self.input_mode = InputMode::Normal,
+            InputMode::Detail(_) => {
+                if key.code == KeyCode::Esc {
+                    self.input_mode = InputMode::Normal;
+                }
+            },
+            InputMode::Archive(state) => match key.code {
+                KeyCode::Up | KeyCode::Char('k') => state.select_previous(),
+                KeyCode::Down | KeyCode::Char('j') => state.select_next(),
+                KeyCode::Enter => {
+                    let Some(archived) = state.selected().filter(|archived| *archived < self.counter_list.archived.len()) else {
+                        return Ok(());
+                    };
+                    let counter = self.counter_list.archived[archived].clone();
+                    let index = self.counter_list.counters.len();
+                    self.input_mode = InputMode::Normal;
+                    self.perform(Action::Unarchive { archived, index, counter });
+                    self.counter_list.refresh_view(Some(index));
+                }
+                KeyCode::Esc => self.input_mode = InputMode::Normal,
+                _ => {}
+            },
+            InputMode::OpenFile(names, state) => match key.code {
+                KeyCode::Up | KeyCode::Char('k') => state.select_previous(),
+                KeyCode::Down | KeyCode::Char('j') => state.select_next(),
+                KeyCode::Enter => {
+                    let Some(name) = state.selected().and_then(|selected| names.get(selected)).cloned() else {
+                        return Ok(());
+                    };
+                    self.input_mode = InputMode::Normal;
+                    if let Err(error) = self.open_file(&name) {
+                        self.notify(format!("{:#}", error));
+                    }
+                }
+                KeyCode::Esc => self.input_mode = InputMode::Normal,
+                _ => {}
+            },
+            InputMode::ConfirmQuit => match key.code {
+                KeyCode::Char('s') => {
+                    self.flush()?;
+                    self.should_exit = true;
+                }
+                KeyCode::Char('d') => {
+                    // Pretend everything is saved so the flush on exit doesn't write anything.
+                    self.dirty = false;
+                    self.saved_selection = self.counter_list.selected_index();
+                    self.should_exit = true;
+                }
+                KeyCode::Esc => self.input_mode = InputMode::Normal,
+                _ => {}
+            },
+            InputMode::Filter(input) => match key.code {
+                KeyCode::Up => self.counter_list.select_previous(),
+                KeyCode::Down => self.counter_list.select_next(),
+                KeyCode::Enter => self.input_mode = InputMode::Normal,
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.set_filter("");
+                }
+                _ => {
+                    input.handle_event(&Event::Key(key));
+                    let filter = input.value().to_owned();
+                    self.set_filter(&filter);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether a Normal mode key only looks at the counters, so it still works read-only. Keys
+    /// that lead to other modes are let through only if nothing in that mode changes anything.
+    fn allowed_when_readonly(&self, key: &KeyEvent) -> bool {
+        if self.config.keys.quit.matches(key) {
+            return true;
+        }
+        match key.code {
+            KeyCode::Up | KeyCode::Down => !key.modifiers.contains(KeyModifiers::SHIFT),
+            KeyCode::Char('t') => key.modifiers.contains(KeyModifiers::CONTROL),
+            KeyCode::PageUp | KeyCode::PageDown | KeyCode::Home | KeyCode::End | KeyCode::Enter | KeyCode::Esc => true,
+            KeyCode::Char('j' | 'k' | 'g' | 'G' | 'y' | 'o' | '%' | 'H' | 'B' | 'f' | '?' | ':' | 'S' | '/') => true,
+            _ => false,
+        }
+    }
+
+    /// Every Normal mode binding with what it does, for the footer and the help overlay.
+    fn normal_bindings(&self) -> Vec<(String, &'static str)> {
+        let keys = &self.config.keys;
+        let mut bindings = vec![
+            ("?".to_owned(), "show all keys"),
+            ("↓↑/jk".to_owned(), "move"),
+            ("PageUp/PageDown".to_owned(), "move a page"),
+            ("gg/G or Home/End".to_owned(), "go to the top/bottom"),
+            (keys.increment.to_string(), "increment"),
+            (keys.decrement.to_string(), "decrement"),
+            (keys.add.to_string(), "add"),
+            (keys.subtract.to_string(), "subtract"),
+            ("shift+→/←".to_owned(), "change by 10 steps"),
+            ("m".to_owned(), "multiply or divide"),
+            ("=".to_owned(), "set the value"),
+            ("i".to_owned(), "edit the value in place"),
+            (keys.new.to_string(), "make a new counter"),
+            (keys.delete.to_string(), if self.config.vim_delete { "delete (press twice)" } else { "delete" }),
+            ("r".to_owned(), "rename"),
+            ("0".to_owned(), "reset"),
+            ("R".to_owned(), "reset every counter"),
+            ("t".to_owned(), "set the step"),
+            ("T".to_owned(), "set a target"),
+            ("[/]".to_owned(), "set a minimum/maximum"),
+            (",/.".to_owned(), "change decimals"),
+            ("C".to_owned(), "change color"),
+            ("F".to_owned(), "show as decimal, hex, binary or a duration"),
+            ("ctrl+t".to_owned(), "switch the theme"),
+            ("b".to_owned(), "set the group"),
+            ("N".to_owned(), "write a note"),
+            ("#".to_owned(), "edit tags"),
+            ("J/K".to_owned(), "reorder"),
+            ("f".to_owned(), "jump by first letter"),
+            ("o".to_owned(), "sort"),
+            ("%".to_owned(), "show shares of the total"),
+            ("H".to_owned(), "show the clock"),
+            ("B".to_owned(), "show as a bar chart"),
+            ("/".to_owned(), "filter"),
+            ("u/ctrl+r".to_owned(), "undo and redo"),
+            ("U".to_owned(), "bring back the last deleted"),
+            ("enter".to_owned(), "show details"),
+            ("S".to_owned(), "show statistics"),
+            ("c".to_owned(), "duplicate"),
+            ("x/X".to_owned(), "archive/show the archive"),
+            ("O".to_owned(), "open another save"),
+            ("L".to_owned(), "lock or unlock"),
+            ("P".to_owned(), "pin to the top or unpin"),
+            ("D".to_owned(), "reset daily or not"),
+            ("p".to_owned(), "start or stop counting seconds"),
+            ("y".to_owned(), "copy the value"),
+            (":".to_owned(), "type a command"),
+            ("e/E".to_owned(), "export to CSV/Markdown"),
+            ("w".to_owned(), "save now"),
+            ("ctrl+s".to_owned(), "save as"),
+            ("W".to_owned(), "toggle autosave"),
+            (keys.quit.to_string(), "exit"),
+        ];
+        if self.config.quick_add {
+            let reset = bindings.iter().position(|(keys, _)| keys == "0").map_or(bindings.len(), |reset| reset + 1);
+            bindings.insert(reset, ("1-9".to_owned(), "add a preset amount"));
+        }
+        bindings
+    }
+
+    fn name_rejected(&self, name: &str, except: Option<usize>) -> bool {
+        self.counter_list.resolve_name(name.trim(), except, self.config.duplicate_names).is_none()
+    }
+
+    fn render_footer(&self, area: Rect, buf: &mut Buffer) {
+        let keys = &self.config.keys;
+        let description = match &self.input_mode {
             InputMode::Normal => {
+                if self.counter_list.counters.is_empty() {
+                    format!("Use {} to make a new counter, ? for help, and {} to exit.", keys.new, keys.quit)
+                }
+                else {
+                    let bindings: Vec<String> = self
+                        .normal_bindings()
+                        .iter()
+                        .map(|(keys, action)| format!("{} to {}", keys, action))
+                        .collect();
+                    format!("Use {}.", bindings.join(", "))
+                }
+            }
+            InputMode::Help(_) => "Use ↓↑/jk or PageUp/PageDown to scroll, and any other key to close the help.".to_owned(),
+            InputMode::Stats => "Press any key to close the statistics.".to_owned(),
+            InputMode::Command(_) => "Type a command: new, rename, set, add, sub, delete, move, countdown, weight, tag, clear, goal, saveas, undo, redo, save, export csv, export md, export jsonl or quit. Use enter to run it and esc to cancel.".to_owned(),
+            InputMode::Jump => "Type the first letter of a counter to jump to it, or esc to cancel.".to_owned(),
+            InputMode::ConfirmQuit => "Use s to save and exit, d to exit without saving, and esc to return.".to_owned(),
+            InputMode::Detail(_) => "Use esc to return to the list.".to_owned(),
+            InputMode::Archive(_) => "Use ↓↑/jk to move, enter to bring a counter back to the list and esc to return.".to_owned(),
+            InputMode::OpenFile(_, _) => "Use ↓↑/jk to move, enter to save this file and open the selected one, and esc to return.".to_owned(),
+            InputMode::NewCounter(input) if self.name_rejected(split_initial_value(input.value()).0, None) => {
+                format!("A counter named '{}' already exists.", split_initial_value(input.value()).0)
+            }
+            InputMode::NewCounter(_) => "Type or paste (ctrl+v) a new counter name, with =value to start from a value. Use enter to add and esc to return.".to_owned(),
+            InputMode::Rename(input, index) if self.name_rejected(input.value(), Some(*index)) => format!("A counter named '{}' already exists.", input.value().trim()),
+            InputMode::Rename(_, _) => "Type the new name. Use enter to rename and esc to cancel.".to_owned(),
+            InputMode::SaveAs(_) => "Type a name to save as, with .toml for TOML. Use enter to save and esc to cancel.".to_owned(),
+            InputMode::ConfirmOverwrite(_) => "Use y to replace the file, and n or esc to cancel.".to_owned(),
+            InputMode::EditStep(_, _) => "Type the step size. Use enter to set it and esc to cancel.".to_owned(),
+            InputMode::SetTarget(_, _) => "Type the target. Use enter to set it, or leave it empty to clear it, and esc to cancel.".to_owned(),
+            InputMode::SetLimit(_, _, limit) => {
+                let limit = match limit {
+                    Limit::Min => "minimum",
+                    Limit::Max => "maximum",
+                };
+                format!("Type the {}. Use enter to set it, or leave it empty to clear it, and esc to cancel.", limit)
+            }
+            InputMode::SetGroup(_, _) => "Type a group name. Use enter to set it, or leave it empty to ungroup, and esc to cancel.".to_owned(),
+            InputMode::EditTags(_, _) => "Type tags separated by commas. Filter by one with / and #tag, or the tag command. Use enter to save them and esc to cancel.".to_owned(),
+            InputMode::EditNote(_, _) => "Type a note about the counter, shown in its details. Use enter to save it, or leave it empty to remove it, and esc to cancel.".to_owned(),
+            InputMode::ConfirmDelete(_) => "Use y to delete the counter, and n or esc to keep it.".to_owned(),
+            InputMode::ConfirmClear => "Use y to delete the counters, and n or esc to keep them.".to_owned(),
+            InputMode::ConfirmReset => "Use y to reset the counters, and n or esc to keep their values.".to_owned(),
+            InputMode::Filter(_) => "Type to filter. Use ↓↑ to move, enter to keep the filter and esc to clear it.".to_owned(),
+            InputMode::Adding(_, sign) => match sign {
+                AddingModeSign::Positive => format!("Use ↓↑/jk to move, {} to subtract instead, Type or paste (p) a number like -5 or an expression, then enter to add and esc to return", keys.subtract),
+                AddingModeSign::Negative => format!("Use ↓↑/jk to move, {} to add instead, Type or paste (p) a number like -5 or an expression, then enter to subtract and esc to return", keys.add),
+            },
+            InputMode::SetValue(_) => "Use ↓↑/jk to move, Type the new value, then enter to set it and esc to return".to_owned(),
+            InputMode::EditInline(_, _) => "Type the new value. Use enter to set it and esc to cancel.".to_owned(),
+            InputMode::Multiply(_, operation) => match operation {
+                MultiplyModeOperation::Multiply => "Use ↓↑/jk to move, / to divide instead, Type a factor, then enter to multiply and esc to return".to_owned(),
+                MultiplyModeOperation::Divide => "Use ↓↑/jk to move, * to multiply instead, Type a divisor, then enter to divide and esc to return".to_owned(),
+            }
+        };
+        let description = self.message.as_ref().map_or(description, |(message, _)| message.clone());
+        let description = if self.readonly {
+            format!("[read-only] {}", description)
+        }
+        else if self.autosave {
+            description
+        }
+        else {
+            format!("[autosave off] {}", description)
+        };
+        Paragraph::new(description).centered().fg(self.theme.footer).render(area, buf);
+    }
+
+    fn render_total(&self, area: Rect, buf: &mut Buffer) {
+        let (total, total_precision) = self.counter_list.total();
+        let separator = &self.config.thousands_separator;
+        let Some(goal) = self.goal else {
+            Paragraph::new(format!("Total: {}", format_grouped(total, total_precision, separator)))
+                .centered()
+                .render(area, buf);
+            return;
+        };
+
+        let precision = total_precision.max(goal.precision);
+        let total = rescale(total, total_precision, precision);
+        let amount = rescale(goal.amount, goal.precision, precision);
+        let ratio = if amount > 0 { (total as f64 / amount as f64).clamp(0.0, 1.0) } else { 1.0 };
+        let remaining = amount.saturating_sub(total);
+        let status = if remaining >= 0 {
+            format!("{} to go", format_grouped(remaining, precision, separator))
+        }
+        else {
+            format!("{} over", format_grouped(remaining.saturating_neg(), precision, separator))
+        };
+        let label = format!(
+            "Total: {} of {} ({:.0}%), {}",
+            format_grouped(total, precision, separator),
+            format_grouped(amount, precision, separator),
+            ratio * 100.0,
+            status,
+        );
+        Gauge::default()
+            .ratio(ratio)
+            .label(label)
+            .gauge_style(Style::new().fg(Color::Green))
+            .render(area, buf);
+    }
+
+    fn render_list(&mut self, area: Rect, buf: &mut Buffer) {
+        if self.show_chart {
+            self.render_chart(area, buf);
+            return;
+        }
+
+        let title = if self.counter_list.filter.is_empty() {
+            self.counter_list.sort.title().to_owned()
+        }
+        else {
+            format!("{} matching '{}'", self.counter_list.sort.title(), self.counter_list.filter)
+        };
+        let mut block = self.theme.block(title.clone()).title_top(self.file_title(area, &title));
+        if let Some(clock) = self.clock_title(area, &title) {
+            block = block.title_top(clock);
+        }
+
+        // Shares are of the total size of every counter, so negative counts don't push the
+        // others' shares past 100%.
+        let shares_total = Some(self.counter_list.counters.iter().map(|counter| counter.value().abs()).sum::<f64>())
+            .filter(|total| self.show_shares && *total != 0.0);
+
+        // Iterate through all elements in the `items` and stylize them.
+        let items: Vec<ListItem> = self
+            .counter_list
+            .view
+            .iter()
+            .map(|row| match row {
+                Row::Header(group) => {
+                    let (total, precision) = self.counter_list.group_total(group.as_deref());
+                    let name = group.as_deref().unwrap_or("Ungrouped");
+                    let total = format_grouped(total, precision, &self.config.thousands_separator);
+                    ListItem::new(Line::raw(format!("— {} (total: {}) —", name, total)).bold())
+                }
+                Row::Counter(index) => {
+                    let counter = &self.counter_list.counters[*index];
+                    let share = shares_total.map(|total| counter.value().abs() / total);
+                    counter.list_item(&self.config.thousands_separator, share)
+                }
+            })
+            .collect();
+
+        // Create a List from all list items and highlight the currently selected one
+        let list = highlighted_list(items, block, &self.theme, &self.config);
+
+        // We need to disambiguate this trait method as both `Widget` and `StatefulWidget` share the
+        // same method name `render`.
+        StatefulWidget::render(list, area, buf, &mut self.counter_list.state);
+
+        self.counter_list.page_height = area.height.saturating_sub(2) as usize;
+        self.counter_list.area = area;
+        let scrollbar_area = area.inner(Margin::new(0, 1));
+        // The scrollbar panics when it has no room, which happens in a tiny terminal.
+        if self.counter_list.view.len() > self.counter_list.page_height && !scrollbar_area.is_empty() {
+            let mut scrollbar_state = ScrollbarState::new(self.counter_list.view.len().saturating_sub(self.counter_list.page_height))
+                .position(self.counter_list.state.offset())
+                .viewport_content_length(self.counter_list.page_height);
+            Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(None)
+                .end_symbol(None)
+                .render(scrollbar_area, buf, &mut scrollbar_state);
+        }
+    }
+
+    /// The name of the open save, for the corner of a panel with a centered `title`. Long names
+    /// are cut short so they don't run into the title.
+    fn file_title(&self, area: Rect, title: &str) -> Line<'static> {
+        let name = match &self.save_state {
+            SaveState::Save(path) => path.file_stem().map_or_else(String::new, |stem| stem.to_string_lossy().into_owned()),
+            SaveState::DoNotSave => "unsaved session".to_owned(),
+        };
+        // The space left of the title, less the corner and a gap on either side.
+        let room = (area.width as usize).saturating_sub(title.chars().count()) / 2;
+        let room = room.saturating_sub(3);
+        let name = if name.chars().count() <= room {
+            name
+        }
+        else if room > 1 {
+            format!("{}…", name.chars().take(room - 1).collect::<String>())
+        }
+        else {
+            String::new()
+        };
+        Line::raw(name).left_aligned().dim()
+    }
+
+    /// The time and how long the session has run, for the corner opposite `file_title`. Left out
+    /// when it's turned off or there's no room for it.
+    fn clock_title(&self, area: Rect, title: &str) -> Option<Line<'static>> {
+        if !self.show_clock {
+            return None;
+        }
+        let elapsed = self.started.elapsed().as_secs();
+        let clock = format!("{} · {}:{:02}:{:02}", Local::now().format("%H:%M"), elapsed / 3600, elapsed / 60 % 60, elapsed % 60);
+        let room = (area.width as usize).saturating_sub(title.chars().count()) / 2;
+        (clock.chars().count() <= room.saturating_sub(3)).then(|| Line::raw(clock).right_aligned().dim())
+    }
+
+    /// The counters in the view as horizontal bars, scrolled to keep the selected one in sight.
+    fn render_chart(&mut self, area: Rect, buf: &mut Buffer) {
+        let title = format!("{} chart", self.counter_list.sort.title());
+        let mut block = self.theme.block(title.clone()).title_top(self.file_title(area, &title));
+        if let Some(clock) = self.clock_title(area, &title) {
+            block = block.title_top(clock);
+        }
+
+        let rows: Vec<usize> = (0..self.counter_list.view.len()).filter(|row| self.counter_list.counter_at(*row).is_some()).collect();
+        let height = area.height.saturating_sub(2) as usize;
+        let selected = self.counter_list.state.selected().and_then(|selected| rows.iter().position(|row| *row == selected));
+        let skip = selected.map_or(0, |selected| (selected + 1).saturating_sub(height));
+        // The chart panics when a label doesn't leave room for the bars, so names get at most half
+        // of the width inside the border.
+        let label_room = (area.width.saturating_sub(2) / 2) as usize;
+
+        let bars: Vec<Bar> = rows
+            .iter()
+            .enumerate()
+            .skip(skip)
+            .map(|(position, row)| {
+                let counter = &self.counter_list.counters[self.counter_list.counter_at(*row).expect("rows only holds counters")];
+                // Bars can't go below zero, so negative counts get an empty bar with a red value.
+                let value = (counter.value().max(0.0) * 10f64.powi(MAX_PRECISION as i32)) as u64;
+                let mut bar = Bar::default()
+                    .value(value)
+                    .text_value(format_grouped(counter.count, counter.precision, &self.config.thousands_separator))
+                    .label(Line::raw(shorten(&counter.name, label_room)));
+                if counter.count < 0 {
+                    bar = bar.value_style(Style::new().red());
+                }
+                if Some(position) == selected {
+                    bar = bar.style(Style::new().bold().reversed());
+                }
+                bar
+            })
+            .collect();
+
+        BarChart::default()
+            .block(block)
+            .direction(Direction::Horizontal)
+            .bar_width(1)
+            .bar_gap(0)
+            .data(BarGroup::default().bars(&bars))
+            .render(area, buf);
+
+        self.counter_list.page_height = height;
+        // Clicks are mapped to rows of the list, which the chart doesn't line up with.
+        self.counter_list.area = Rect::default();
+    }
+
+    /// Draws the value being edited over the selected row, in place of its count.
+    fn render_inline_edit(&self, buf: &mut Buffer) {
+        let InputMode::EditInline(input, index) = &self.input_mode else {
+            return;
+        };
+        let Some(area) = self.counter_list.state.selected().and_then(|row| self.counter_list.row_area(row)) else {
+            return;
+        };
+        // Leave the highlight symbol in front of the row.
+        let symbol_width = Line::raw(self.config.highlight_symbol.as_str()).width() as u16;
+        let area = Rect { x: area.x + symbol_width.min(area.width), width: area.width.saturating_sub(symbol_width), ..area };
+        let name = &self.counter_list.counters[*index].name;
+
+        Clear.render(area, buf);
+        Line::from(vec![Span::raw(input.value().to_owned()).underlined(), Span::raw(format!(": {}", name))])
+            .style(self.theme.highlight)
+            .render(area, buf);
+    }
+
+    /// Every key, scrolled to the offset in `InputMode::Help` when they don't all fit.
+    fn render_help(&mut self, area: Rect, buf: &mut Buffer) {
+        let bindings = self.normal_bindings();
+        let key_width = bindings.iter().map(|(keys, _)| keys.chars().count()).max().unwrap_or(0);
+        let lines: Vec<Line> = bindings
+            .iter()
+            .map(|(keys, action)| Line::raw(format!("{:>key_width$}  {}", keys, action)))
+            .collect();
+
+        self.help_page = (area.height as usize).saturating_sub(2);
+        let last_offset = lines.len().saturating_sub(self.help_page);
+        let offset = match &mut self.input_mode {
+            InputMode::Help(offset) => {
+                *offset = (*offset).min(last_offset);
+                *offset
+            }
+            _ => 0,
+        };
+        let title = if last_offset > 0 { "Help (↓↑ to scroll)" } else { "Help" };
+        render_popup(&self.theme, title, lines, offset, area, buf);
+    }
+
+    fn render_stats(&self, area: Rect, buf: &mut Buffer) {
+        let counters = &self.counter_list.counters;
+        let lines = if counters.is_empty() {
+            vec![Line::raw("No counters yet")]
+        }
+        else {
+            let (total, precision) = self.counter_list.total();
+            let average = counters.iter().map(Counter::value).sum::<f64>() / counters.len() as f64;
+            let min = counters.iter().min_by(|a, b| a.value().total_cmp(&b.value())).expect("counters isn't empty");
+            let max = counters.iter().max_by(|a, b| a.value().total_cmp(&b.value())).expect("counters isn't empty");
+            vec![
+                Line::raw(format!("Counters: {}", counters.len())),
+                Line::raw(format!("Sum: {}", format_amount(total, precision))),
+                Line::raw(format!("Weighted sum: {}", format_weighted(weighted_total(counters)))),
+                Line::raw(format!("Average: {:.2}", average)),
+                Line::raw(format!("Minimum: {} ({})", format_amount(min.count, min.precision), min.name)),
+                Line::raw(format!("Maximum: {} ({})", format_amount(max.count, max.precision), max.name)),
+            ]
+        };
+
+        render_popup(&self.theme, "Statistics", lines, 0, area, buf);
+    }
+
+    fn render_detail(&self, index: usize, area: Rect, buf: &mut Buffer) {
+        let block = self.theme.block("Details");
+
+        let lines = self.counter_list.counters.get(index).map(Counter::detail_lines).unwrap_or_default();
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(block)
+            .render(area, buf);
+    }
+
+    fn render_archive(&mut self, area: Rect, buf: &mut Buffer) {
+        let InputMode::Archive(state) = &mut self.input_mode else {
+            return;
+        };
+        let block = self.theme.block("Archive");
+
+        if self.counter_list.archived.is_empty() {
+            Paragraph::new("Nothing archived yet. Use x in the list to archive a counter.")
+                .centered()
+                .block(block)
+                .render(area, buf);
+            return;
+        }
+
+        let items: Vec<ListItem> = self
+            .counter_list
+            .archived
+            .iter()
+            .map(|counter| counter.list_item(&self.config.thousands_separator, None))
+            .collect();
+        let list = highlighted_list(items, block, &self.theme, &self.config);
+        StatefulWidget::render(list, area, buf, state);
+    }
+
+    fn render_open_file(&mut self, area: Rect, buf: &mut Buffer) {
+        let InputMode::OpenFile(names, state) = &mut self.input_mode else {
+            return;
+        };
+        let block = self.theme.block("Open");
+
+        if names.is_empty() {
+            Paragraph::new("No saves in the save directory yet.")
+                .centered()
+                .block(block)
+                .render(area, buf);
+            return;
+        }
+
+        let items: Vec<ListItem> = names.iter().map(|name| ListItem::new(name.clone())).collect();
+        let list = highlighted_list(items, block, &self.theme, &self.config);
+        StatefulWidget::render(list, area, buf, state);
+    }
+
+    fn render_input(&mut self, area: Rect, buf: &mut Buffer) {
+        match &self.input_mode {
+            InputMode::Normal | InputMode::Jump | InputMode::Help(_) | InputMode::Stats | InputMode::Detail(_) | InputMode::Archive(_) | InputMode::OpenFile(_, _) | InputMode::EditInline(_, _) => {}
+            InputMode::NewCounter(input) => {
+                let block = self.theme.block("New Counter");
+
+                Paragraph::new(input.value())
+                    .centered()
+                    .block(block)
+                    .render(area, buf);
+            }
+            InputMode::Rename(input, _) => {
+                let block = self.theme.block("Rename");
+
+                Paragraph::new(input.value())
+                    .centered()
+                    .block(block)
+                    .render(area, buf);
+            }
+            InputMode::SaveAs(input) => {
+                let block = self.theme.block("Save As");
+
+                Paragraph::new(input.value())
+                    .centered()
+                    .block(block)
+                    .render(area, buf);
+            }
+            InputMode::ConfirmOverwrite(path) => {
+                let block = self.theme.block("Replace");
+
+                let name = path.file_name().map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+                Paragraph::new(format!("Replace {}? (y/n)", name))
+                    .centered()
+                    .block(block)
+                    .render(area, buf);
+            }
+            InputMode::EditStep(input, _) => {
+                let block = self.theme.block("Step");
+
+                Paragraph::new(input.value())
+                    .centered()
+                    .block(block)
+                    .render(area, buf);
+            }
+            InputMode::SetGroup(input, _) => {
+                let block = self.theme.block("Group");
+
+                Paragraph::new(input.value())
+                    .centered()
+                    .block(block)
+                    .render(area, buf);
+            }
+            InputMode::EditNote(input, _) => {
+                let block = self.theme.block("Note");
+
+                Paragraph::new(input.value())
+                    .centered()
+                    .block(block)
+                    .render(area, buf);
+            }
+            InputMode::EditTags(input, _) => {
+                let block = self.theme.block("Tags");
+
+                Paragraph::new(input.value())
+                    .centered()
+                    .block(block)
+                    .render(area, buf);
+            }
+            InputMode::ConfirmQuit => {
+                let block = self.theme.block("Quit");
+
+                Paragraph::new("Unsaved changes — save (s) / discard (d) / cancel (Esc)")
+                    .centered()
+                    .block(block)
+                    .render(area, buf);
+            }
+            InputMode::SetLimit(input, _, limit) => {
+                let block = self.theme.block(match limit {
+                    Limit::Min => "Minimum",
+                    Limit::Max => "Maximum",
+                });
+
+                Paragraph::new(input.value())
+                    .centered()
+                    .block(block)
+                    .render(area, buf);
+            }
+            InputMode::SetTarget(input, _) => {
+                let block = self.theme.block("Target");
+
+                Paragraph::new(input.value())
+                    .centered()
+                    .block(block)
+                    .render(area, buf);
+            }
+            InputMode::ConfirmDelete(index) => {
+                let block = self.theme.block("Delete");
+
+                let name = self.counter_list.counters.get(*index).map_or("", |counter| &counter.name);
+                Paragraph::new(format!("Delete '{}'? (y/n)", name))
+                    .centered()
+                    .block(block)
+                    .render(area, buf);
+            }
+            InputMode::ConfirmClear => {
+                let block = self.theme.block("Clear");
+
+                let count = self.clearable().len();
+                let counters = if count == 1 { "counter" } else { "counters" };
+                Paragraph::new(format!("Delete {} {}? (y/n)", count, counters))
+                    .centered()
+                    .block(block)
+                    .render(area, buf);
+            }
+            InputMode::ConfirmReset => {
+                let block = self.theme.block("Reset");
+
+                let count = self.resettable().len();
+                let counters = if count == 1 { "counter" } else { "counters" };
+                Paragraph::new(format!("Reset {} {} to zero? (y/n)", count, counters))
+                    .centered()
+                    .block(block)
+                    .render(area, buf);
+            }
+            InputMode::Command(input) => {
+                let block = self.theme.block("Command");
+
+                Paragraph::new(format!(":{}", input.value()))
+                    .centered()
+                    .block(block)
+                    .render(area, buf);
+            }
+            InputMode::Filter(input) => {
+                let block = self.theme.block("Filter");
+
+                Paragraph::new(input.value())
+                    .centered()
+                    .block(block)
+                    .render(area, buf);
+            }
+            InputMode::Adding(input, sign) => {
+                let block = self.theme.block(match sign {
+                    AddingModeSign::Positive => "Adding",
+                    AddingModeSign::Negative => "Subtracting"
+                });
+
+                // What the count would become, so a mistyped amount can be caught before enter.
+                let mut line = Line::raw(input.value().to_owned());
+                let counter = self.counter_list.selected_index().map(|index| &self.counter_list.counters[index]);
+                let value = counter.and_then(|counter| evaluate(&amount_text(input.value(), &self.config), counter.precision));
+                if let (Some(counter), Some(value)) = (counter, value) {
+                    let count = match sign {
+                        AddingModeSign::Positive => counter.count.saturating_add(value),
+                        AddingModeSign::Negative => counter.count.saturating_sub(value),
+                    };
+                    let separator = &self.config.thousands_separator;
+                    let preview = format!(
+                        "  ({} → {})",
+                        format_grouped(counter.count, counter.precision, separator),
+                        format_grouped(counter.clamp(count), counter.precision, separator),
+                    );
+                    line.push_span(Span::raw(preview).dim());
+                }
+                Paragraph::new(line)
+                    .centered()
+                    .block(block)
+                    .render(area, buf);
+            }
+            InputMode::SetValue(input) => {
+                let block = self.theme.block("Set value");
+
+                Paragraph::new(input.value())
+                    .centered()
+                    .block(block)
+                    .render(area, buf);
+            }
+            InputMode::Multiply(input, operation) => {
+                let block = self.theme.block(match operation {
+                    MultiplyModeOperation::Multiply => "Multiplying",
+                    MultiplyModeOperation::Divide => "Dividing"
+                });
+
+                Paragraph::new(input.value())
+                    .centered()
+                    .block(block)
+                    .render(area, buf);
+            }
+        }
+    }
+}
+
+/// Draws lines in a bordered box centered over `area`, sized to fit them, or scrolled down by
+/// `scroll` lines when they're taller than it.
+fn render_popup(theme: &Theme, title: &str, lines: Vec<Line>, scroll: usize, area: Rect, buf: &mut Buffer) {
+    let width = lines.iter().map(Line::width).max().unwrap_or(0).max(title.chars().count()) as u16 + 4;
+    // Taller than the area, it would lose its bottom border, so it's scrolled instead.
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let [popup_area] = Layout::horizontal([Constraint::Length(width)]).flex(Flex::Center).areas(area);
+    let [popup_area] = Layout::vertical([Constraint::Length(height)]).flex(Flex::Center).areas(popup_area);
+
+    let block = theme.block(title);
+
+    Clear.render(popup_area, buf);
+    Paragraph::new(lines)
+        .block(block)
+        .scroll((scroll as u16, 0))
+        .render(popup_area, buf);
+}
+
+/// Cuts `text` down to `width` columns, ending it with `…` when anything was left off.
+fn shorten(text: &str, width: usize) -> String {
+    if Line::raw(text).width() <= width {
+        return text.to_owned();
+    }
+    let mut shortened = String::new();
+    for char in text.chars() {
+        shortened.push(char);
+        if Line::raw(shortened.as_str()).width() + 1 > width {
+            shortened.pop();
+            break;
+        }
+    }
+    if width > 0 {
+        shortened.push('…');
+    }
+    shortened
+}
+
+/// Splits new counter input like `name=10` into the name and the starting count, along with
+/// the precision the count needs. Only the last `=` counts, and only if a number follows it, so
+/// names can still contain `=`.
+fn split_initial_value(text: &str) -> (&str, Option<(i64, u32)>) {
+    if let Some((name, value)) = text.rsplit_once('=') {
+        let value = value.trim();
+        let precision = value.split_once('.').map_or(0, |(_, decimals)| decimals.len() as u32);
+        if precision <= MAX_PRECISION {
+            if let Some(count) = parse_amount(value, precision) {
+                return (name.trim(), Some((count, precision)));
+            }
+        }
+    }
+    (text.trim(), None)
+}
+
+/// Replaces a leading `~` in a path with the home directory.
+fn expand_home(path: PathBuf) -> anyhow::Result<PathBuf> {
+    match path.strip_prefix("~") {
+        Ok(rest) => Ok(dirs::home_dir().context("Couldn't get home directory")?.join(rest)),
+        Err(_) => Ok(path),
+    }
+}
+
+/// Whether a character can be typed into an expression in Adding mode.
+fn is_expression_char(char: char) -> bool {
+    char.is_ascii_digit() || ".+-*/() ".contains(char)
+}
+
+/// Whether a character can be typed into an amount while adding: anything in an expression,
+/// and thousands separators when `lenient_numbers` is on.
+fn is_amount_char(char: char, config: &Config) -> bool {
+    is_expression_char(char) || config.lenient_numbers && (char == ',' || config.thousands_separator.contains(char))
+}
+
+/// An amount typed while adding, ready to evaluate. With `lenient_numbers` on, thousands
+/// separators are dropped, and with `.` as the separator `,` becomes the decimal point.
+fn amount_text<'a>(text: &'a str, config: &Config) -> Cow<'a, str> {
+    if !config.lenient_numbers {
+        return Cow::Borrowed(text);
+    }
+    let separator = config.thousands_separator.as_str();
+    if separator == "." {
+        return Cow::Owned(text.replace('.', "").replace(',', "."));
+    }
+    let text = text.replace(',', "");
+    Cow::Owned(if separator.is_empty() { text } else { text.replace(separator, "") })
+}
+
+/// The text on the clipboard, if there is any.
+fn clipboard_text(clipboard: &mut Option<Clipboard>) -> Option<String> {
+    if clipboard.is_none() {
+        *clipboard = Clipboard::new().ok();
+    }
+    clipboard.as_mut()?.get_text().ok()
+}
+
+/// Types the clipboard's text into an input, skipping characters the input doesn't accept.
+fn paste(input: &mut Input, clipboard: &mut Option<Clipboard>, accepts: impl Fn(char) -> bool) {
+    if let Some(text) = clipboard_text(clipboard) {
+        for char in text.trim().chars().filter(|char| accepts(*char)) {
+            input.handle(InputRequest::InsertChar(char));
+        }
+    }
+}
+
+/// Quotes a CSV field per RFC 4180 when it contains a delimiter, quote or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    }
+    else {
+        field.to_owned()
+    }
+}
+
+/// Reads counters from `name,count` CSV rows, along with how many rows were skipped for not
+/// having a name and a number. A first row of `name,count` is taken as a header.
+fn read_csv(path: &Path) -> anyhow::Result<(Vec<Counter>, usize)> {
+    let text = fs::read_to_string(path).context(format!("Failed to open file: {}", path.display()))?;
+    let mut rows = parse_csv(&text);
+    if rows.first().is_some_and(|row| row.len() == 2 && row[0].eq_ignore_ascii_case("name") && row[1].eq_ignore_ascii_case("count")) {
+        rows.remove(0);
+    }
+
+    let (mut counters, mut skipped) = (vec![], 0);
+    for row in rows {
+        let [name, count] = row.as_slice() else {
+            skipped += 1;
+            continue;
+        };
+        // The same rules as `name=10` for a new counter.
+        match split_initial_value(&format!("{}={}", name, count)) {
+            (name, Some((count, precision))) if !name.trim().is_empty() => {
+                let mut counter = Counter::new(name.trim());
+                counter.count = count;
+                counter.precision = precision;
+                counters.push(counter);
+            }
+            _ => skipped += 1,
+        }
+    }
+    Ok((counters, skipped))
+}
+
+/// Splits CSV text into rows of fields per RFC 4180, undoing the quoting `csv_field` does. Blank
+/// lines are left out.
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(char) = chars.next() {
+        match char {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                if row.iter().any(|field| !field.is_empty()) {
+                    rows.push(std::mem::take(&mut row));
+                }
+                row.clear();
+            }
+            char => field.push(char),
+        }
+    }
+    row.push(field);
+    if row.iter().any(|field| !field.is_empty()) {
+        rows.push(row);
+    }
+    rows
+}
+
+/// A list with the selected row highlighted the way the theme and config ask.
+fn highlighted_list<'a>(items: Vec<ListItem<'a>>, block: Block<'a>, theme: &Theme, config: &'a Config) -> List<'a> {
+    let spacing = if config.compact_list { HighlightSpacing::WhenSelected } else { HighlightSpacing::Always };
+    List::new(items)
+        .block(block)
+        .highlight_style(theme.highlight)
+        .highlight_symbol(&config.highlight_symbol)
+        .highlight_spacing(spacing)
+}
+
+/// Escapes a Markdown table cell, so pipes in it don't start a new cell and line breaks don't
+/// end the row.
+fn markdown_cell(cell: &str) -> String {
+    cell.replace('|', "\\|").replace(['\n', '\r'], " ")
+}
+
+impl Widget for &mut App {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        buf.set_style(area, Style::new().fg(self.theme.text));
+        if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+            let [message_area] = Layout::vertical([Constraint::Length(2)]).flex(Flex::Center).areas(area);
+            Paragraph::new(format!("Terminal too small, needs {}x{}", MIN_WIDTH, MIN_HEIGHT))
+                .centered()
+                .wrap(Wrap { trim: true })
+                .render(message_area, buf);
+            return;
+        }
+
+        let [main_area, total_area, footer_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1), Constraint::Length(1)]).areas(area);
+
+        let [adding_area, list_area] =
+            Layout::vertical([Constraint::Length(3), Constraint::Fill(1)]).areas(main_area);
+
+        match self.input_mode {
+            InputMode::Normal | InputMode::Jump => {
                 self.render_list(main_area, buf);
             }
-            InputMode::NewCounter(_) => {
-                self.render_input(adding_area, buf);
-                self.render_list(list_area, buf);
+            InputMode::EditInline(_, _) => {
+                self.render_list(main_area, buf);
+                self.render_inline_edit(buf);
+            }
+            InputMode::Help(_) => {
+                self.render_list(main_area, buf);
+                self.render_help(main_area, buf);
+            }
+            InputMode::Stats => {
+                self.render_list(main_area, buf);
+                self.render_stats(main_area, buf);
+            }
+            InputMode::Detail(index) => {
+                self.render_detail(index, main_area, buf);
             }
-            InputMode::Adding(_, _) => {
+            InputMode::Archive(_) => {
+                self.render_archive(main_area, buf);
+            }
+            InputMode::OpenFile(_, _) => {
+                self.render_open_file(main_area, buf);
+            }
+            InputMode::NewCounter(_) | InputMode::Adding(_, _) | InputMode::Multiply(_, _) | InputMode::SetValue(_) | InputMode::Rename(_, _) | InputMode::EditStep(_, _) | InputMode::SetTarget(_, _) | InputMode::SetLimit(_, _, _) | InputMode::SetGroup(_, _) | InputMode::EditNote(_, _) | InputMode::EditTags(_, _) | InputMode::ConfirmDelete(_) | InputMode::ConfirmReset | InputMode::ConfirmClear | InputMode::ConfirmQuit | InputMode::SaveAs(_) | InputMode::ConfirmOverwrite(_) | InputMode::Filter(_) | InputMode::Command(_) => {
                 self.render_input(adding_area, buf);
                 self.render_list(list_area, buf);
             }
         }
 
+        self.render_total(total_area, buf);
         self.render_footer(footer_area, buf);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counter::unix_time;
+
+    /// A temporary session with a counter for each name, the first one selected, and the default
+    /// settings rather than the ones on this machine.
+    fn app_with(names: &[&str]) -> App {
+        let counters = names.iter().map(|name| Counter::new(name)).collect::<Vec<_>>();
+        let selected = (!counters.is_empty()).then_some(0);
+        App::new(SaveFile { counters: Cow::Owned(counters), selected, ..Default::default() }, SaveState::DoNotSave, Config::default())
+    }
+
+    fn press(app: &mut App, code: KeyCode) {
+        app.handle_key(KeyEvent::new(code, KeyModifiers::NONE)).unwrap();
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        for char in text.chars() {
+            press(app, KeyCode::Char(char));
+        }
+    }
+
+    /// A path in the temp directory for a test to write to, unique to the test and the run.
+    fn temp_file(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("tui-counters-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn enter_without_an_amount_keeps_adding() {
+        let mut app = app_with(&["a"]);
+        press(&mut app, KeyCode::Char('a'));
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.counter_list.counters[0].count, 0);
+        assert!(matches!(app.input_mode, InputMode::Adding(_, AddingModeSign::Positive)));
+    }
+
+    #[test]
+    fn increments_clamp_at_the_bounds() {
+        let mut app = app_with(&["a"]);
+        app.counter_list.counters[0].count = i64::MAX - 1;
+        press(&mut app, KeyCode::Char('l'));
+        press(&mut app, KeyCode::Char('l'));
+        assert_eq!(app.counter_list.counters[0].count, i64::MAX);
+
+        app.counter_list.counters[0].count = i64::MIN + 1;
+        press(&mut app, KeyCode::Char(';'));
+        press(&mut app, KeyCode::Char(';'));
+        assert_eq!(app.counter_list.counters[0].count, i64::MIN);
+    }
+
+    #[test]
+    fn adding_clamps_at_the_bounds() {
+        let mut app = app_with(&["a"]);
+        app.counter_list.counters[0].count = i64::MAX - 1;
+        press(&mut app, KeyCode::Char('a'));
+        type_text(&mut app, "5");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.counter_list.counters[0].count, i64::MAX);
+
+        app.counter_list.counters[0].count = i64::MIN + 1;
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Char('s'));
+        type_text(&mut app, "5");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.counter_list.counters[0].count, i64::MIN);
+    }
+
+    #[test]
+    fn huge_typed_amounts_clamp() {
+        let mut app = app_with(&["a"]);
+        press(&mut app, KeyCode::Char('a'));
+        type_text(&mut app, "99999999999999999999999999");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.counter_list.counters[0].count, i64::MAX);
+
+        press(&mut app, KeyCode::Esc);
+        app.counter_list.counters[0].count = i64::MIN + 1;
+        press(&mut app, KeyCode::Char('s'));
+        type_text(&mut app, "99999999999999999999999999");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.counter_list.counters[0].count, i64::MIN);
+
+        assert_eq!(parse_amount("99999999999999999999999999", 0), Some(i64::MAX));
+        assert_eq!(parse_amount("-99999999999999999999999999", 2), Some(-i64::MAX));
+    }
+
+    #[test]
+    fn new_counters_need_a_name() {
+        let mut app = app_with(&[]);
+        press(&mut app, KeyCode::Char('n'));
+        press(&mut app, KeyCode::Enter);
+        type_text(&mut app, "   ");
+        press(&mut app, KeyCode::Enter);
+        assert!(app.counter_list.counters.is_empty());
+        assert!(matches!(app.input_mode, InputMode::NewCounter(_)));
+    }
+
+    #[test]
+    fn new_counter_names_are_trimmed() {
+        let mut app = app_with(&[]);
+        press(&mut app, KeyCode::Char('n'));
+        type_text(&mut app, "  pushups  ");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.counter_list.counters.len(), 1);
+        assert_eq!(app.counter_list.counters[0].name, "pushups");
+
+        assert_eq!(split_initial_value("  laps = 1.5 "), ("laps", Some((15, 1))));
+        assert_eq!(split_initial_value(" = "), ("=", None));
+    }
+
+    #[test]
+    fn zero_resets_the_selected_counter() {
+        let mut app = app_with(&["a"]);
+        press(&mut app, KeyCode::Char('l'));
+        press(&mut app, KeyCode::Char('l'));
+        assert_eq!(app.counter_list.counters[0].count, 2);
+        press(&mut app, KeyCode::Char('0'));
+        assert_eq!(app.counter_list.counters[0].count, 0);
+    }
+
+    #[test]
+    fn csv_export_quotes_names() {
+        let mut app = app_with(&["apples, red", "say \"hi\""]);
+        app.counter_list.counters[0].count = 3;
+        app.counter_list.counters[1].precision = 1;
+        app.counter_list.counters[1].count = -15;
+        let path = temp_file("export.csv");
+        app.export_csv(&path).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(text, "name,count\n\"apples, red\",3\n\"say \"\"hi\"\"\",-1.5\n");
+    }
+
+    #[test]
+    fn declining_a_delete_keeps_the_counter() {
+        let mut app = app_with(&["a"]);
+        press(&mut app, KeyCode::Char('d'));
+        assert!(matches!(app.input_mode, InputMode::ConfirmDelete(0)));
+        press(&mut app, KeyCode::Char('n'));
+        assert!(matches!(app.input_mode, InputMode::Normal));
+        assert_eq!(app.counter_list.counters.len(), 1);
+        assert_eq!(app.counter_list.counters[0].name, "a");
+    }
+
+    #[test]
+    fn setting_a_counter_needs_a_name() {
+        let path = temp_file("set.json");
+        let mut app = App::new(SaveFile::default(), SaveState::Save(path.clone()), Config::default());
+        assert!(app.set_counter("  ", "5").is_err());
+        assert!(app.counter_list.counters.is_empty());
+        assert!(!path.exists());
+    }
+
+    /// Makes a counter named `a` next to an existing one, with `duplicate_names` set.
+    fn add_duplicate(duplicate_names: DuplicateNames) -> App {
+        let mut app = app_with(&["a"]);
+        app.config.duplicate_names = duplicate_names;
+        press(&mut app, KeyCode::Char('n'));
+        type_text(&mut app, "a");
+        press(&mut app, KeyCode::Enter);
+        app
+    }
+
+    #[test]
+    fn duplicate_names_can_be_allowed() {
+        let app = add_duplicate(DuplicateNames::Allow);
+        let names: Vec<&str> = app.counter_list.counters.iter().map(|counter| counter.name.as_str()).collect();
+        assert_eq!(names, ["a", "a"]);
+    }
+
+    #[test]
+    fn duplicate_names_can_be_rejected() {
+        let app = add_duplicate(DuplicateNames::Reject);
+        assert_eq!(app.counter_list.counters.len(), 1);
+        assert!(matches!(&app.input_mode, InputMode::NewCounter(input) if input.value() == "a"));
+    }
+
+    #[test]
+    fn duplicate_names_can_be_suffixed() {
+        let mut app = add_duplicate(DuplicateNames::Suffix);
+        type_text(&mut app, "a");
+        press(&mut app, KeyCode::Enter);
+        let names: Vec<&str> = app.counter_list.counters.iter().map(|counter| counter.name.as_str()).collect();
+        assert_eq!(names, ["a", "a (2)", "a (3)"]);
+    }
+
+    #[test]
+    fn tiny_terminals_get_a_message() {
+        let area = Rect::new(0, 0, MIN_WIDTH - 1, 3);
+        let mut buf = Buffer::empty(area);
+        App::make_temporary().render(area, &mut buf);
+        let text: String = buf.content().iter().map(|cell| cell.symbol()).collect();
+        assert!(text.contains("Terminal too small"), "{:?}", text);
+    }
+
+    #[test]
+    fn markdown_export_escapes_pipes() {
+        let mut app = app_with(&["in | out", "plain"]);
+        app.counter_list.counters[0].count = 1200;
+        let path = temp_file("export.md");
+        app.export_markdown(&path).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(text, "| Name | Count |\n| --- | ---: |\n| in \\| out | 1200 |\n| plain | 0 |\n");
+    }
+
+    #[test]
+    fn locked_counters_keep_their_precision_and_place() {
+        let mut app = app_with(&["a"]);
+        app.counter_list.counters[0].precision = 2;
+        app.counter_list.counters[0].count = 155;
+        app.counter_list.counters[0].locked = true;
+        press(&mut app, KeyCode::Char(','));
+        press(&mut app, KeyCode::Char('x'));
+        assert_eq!(app.counter_list.counters.len(), 1);
+        assert!(app.counter_list.archived.is_empty());
+        assert_eq!((app.counter_list.counters[0].count, app.counter_list.counters[0].precision), (155, 2));
+    }
+
+    /// The rows of the app drawn at a size, as text.
+    fn draw(app: &mut App, width: u16, height: u16) -> Vec<String> {
+        let area = Rect::new(0, 0, width, height);
+        let mut buf = Buffer::empty(area);
+        app.render(area, &mut buf);
+        (0..height).map(|y| (0..width).map(|x| buf.get(x, y).symbol()).collect()).collect()
+    }
+
+    #[test]
+    fn help_scrolls_to_every_key() {
+        let mut app = app_with(&["a"]);
+        press(&mut app, KeyCode::Char('?'));
+        let rows = draw(&mut app, 80, 24);
+        assert!(rows.iter().any(|row| row.contains("show all keys")));
+        // The list's bottom corner and the help's, on the same row.
+        assert!(rows.iter().any(|row| row.matches('╰').count() == 2), "the bottom border is cut off");
+
+        for _ in 0..10 {
+            press(&mut app, KeyCode::PageDown);
+            draw(&mut app, 80, 24);
+        }
+        let rows = draw(&mut app, 80, 24);
+        assert!(rows.iter().any(|row| row.contains("  exit")));
+        assert!(matches!(app.input_mode, InputMode::Help(offset) if offset > 0));
+
+        press(&mut app, KeyCode::Char('x'));
+        assert!(matches!(app.input_mode, InputMode::Normal));
+    }
+
+    #[test]
+    fn saves_keep_their_extension_unless_exports_use_it() {
+        let name = |input_name: &str| App::save_path(input_name).unwrap().file_name().unwrap().to_string_lossy().into_owned();
+        assert_eq!(name("habits"), "habits.json");
+        assert_eq!(name("data/habits.json"), "habits.json");
+        assert_eq!(name("habits.toml"), "habits.toml");
+        assert_eq!(name("/tmp/habits.dat"), "habits.dat");
+        assert_eq!(name("v1.2"), "v1.2");
+        for input_name in ["data.csv", "/tmp/notes.md", "x.jsonl"] {
+            assert!(App::save_path(input_name).is_err());
+        }
+    }
+
+    #[test]
+    fn new_limits_clamp_the_count() {
+        let mut app = app_with(&["a"]);
+        app.counter_list.counters[0].count = 100;
+        press(&mut app, KeyCode::Char(']'));
+        type_text(&mut app, "50");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.counter_list.counters[0].max, Some(50));
+        assert_eq!(app.counter_list.counters[0].count, 50);
+        press(&mut app, KeyCode::Char('u'));
+        assert_eq!(app.counter_list.counters[0].count, 100);
+        assert_eq!(app.counter_list.counters[0].max, None);
+
+        app.counter_list.counters[0].locked = true;
+        press(&mut app, KeyCode::Char('['));
+        assert!(matches!(app.input_mode, InputMode::Normal));
+    }
+
+    #[test]
+    fn long_names_fit_the_chart() {
+        let name = "a".repeat(41);
+        let mut app = app_with(&[&name]);
+        press(&mut app, KeyCode::Char('B'));
+        let rows = draw(&mut app, 40, 20);
+        assert!(rows.iter().any(|row| row.contains(&format!("{}…", "a".repeat(18)))));
+    }
+
+    #[test]
+    fn imports_leave_locked_counters_and_limits_alone() {
+        let path = temp_file("import.json");
+        let import_path = temp_file("import.csv");
+        let mut locked = Counter::new("a");
+        locked.locked = true;
+        locked.count = 3;
+        let mut limited = Counter::new("b");
+        limited.max = Some(10);
+        let counters = vec![locked, limited];
+        let mut app = App::new(SaveFile { counters: Cow::Owned(counters), ..Default::default() }, SaveState::Save(path.clone()), Config::default());
+        fs::write(&import_path, "a,500\nb,500\n").unwrap();
+
+        let summary = app.import(&import_path, false).unwrap();
+        assert!(summary.contains("leaving locked a"));
+        assert_eq!(app.counter_list.counters[0].count, 3);
+        assert_eq!(app.counter_list.counters[1].count, 10);
+        assert_eq!(app.counter_list.counters[1].log.len(), 1);
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&import_path).unwrap();
+    }
+
+    #[test]
+    fn timer_ticks_dont_need_saving() {
+        let mut app = app_with(&["a", "b"]);
+        app.counter_list.counters[0].timer_started = Some(unix_time() - 5);
+        app.catch_up_timers();
+        assert_eq!(app.counter_list.counters[0].count, 5);
+        assert!(!app.dirty);
+
+        app.counter_list.counters[1].countdown = Some(10);
+        app.counter_list.counters[1].count = 3;
+        app.counter_list.counters[1].timer_started = Some(unix_time() - 5);
+        app.catch_up_timers();
+        assert_eq!(app.counter_list.counters[1].count, 0);
+        assert!(app.dirty);
+    }
+
+    #[test]
+    fn goals_are_above_zero() {
+        let mut app = app_with(&["a"]);
+        app.counter_list.counters[0].count = i64::MAX;
+        app.run_command("goal -1").unwrap();
+        assert!(app.goal.is_none());
+        app.run_command("goal 0").unwrap();
+        assert!(app.goal.is_none());
+
+        // Older saves can still hold one.
+        app.goal = Some(Goal { amount: -1, precision: 0 });
+        draw(&mut app, 80, 24);
+    }
+
+    #[test]
+    fn restoring_keeps_the_current_save() {
+        let path = temp_file("restore.json");
+        fs::write(&path, "latest").unwrap();
+        fs::write(backup_path(&path, 0), "backup").unwrap();
+
+        let (backup, kept) = App::restore_backup(path.to_str().unwrap()).unwrap();
+        let kept = kept.unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "backup");
+        assert_eq!(fs::read_to_string(&kept).unwrap(), "latest");
+        for path in [path, backup, kept] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use clap::Parser;
 
 /// Simple program to greet a person
@@ -6,4 +8,39 @@ use clap::Parser;
 pub(crate) struct Args {
     /// The name of the save to open.
     pub(crate) input_name: Option<String>,
+
+    /// Print every counter in the save and exit, without opening the TUI.
+    #[arg(long, requires = "input_name")]
+    pub(crate) list: bool,
+
+    /// Set a counter in the save to a value, creating it if needed, and exit.
+    #[arg(long, num_args = 2, value_names = ["NAME", "VALUE"], allow_hyphen_values = true)]
+    pub(crate) set: Option<Vec<String>>,
+
+    /// Merge the counters from another save file, or a `name,count` CSV file, into the save,
+    /// adding up counters with the same name, and exit.
+    #[arg(long, value_name = "FILE", requires = "input_name")]
+    pub(crate) import: Option<PathBuf>,
+
+    /// With --import, replace the counts of counters with the same name instead of adding them.
+    #[arg(long, requires = "import")]
+    pub(crate) replace: bool,
+
+    /// Replace the save with its most recent backup and exit.
+    #[arg(long, requires = "input_name")]
+    pub(crate) restore: bool,
+
+    /// Print the counters as JSON when the TUI exits, even without a save. The TUI is drawn on
+    /// stderr instead, so stdout can be piped.
+    #[arg(long)]
+    pub(crate) stdout_json: bool,
+
+    /// Open the save without changing it. Keys that would change a counter do nothing, and
+    /// nothing is written.
+    #[arg(long, requires = "input_name", conflicts_with_all = ["set", "import", "restore"])]
+    pub(crate) readonly: bool,
+
+    /// Save and exit after this many seconds without a key press or mouse input.
+    #[arg(long, value_name = "SECONDS")]
+    pub(crate) idle_timeout: Option<u64>,
 }
\ No newline at end of file
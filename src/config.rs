@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::path::PathBuf;
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+
+use crate::theme::{ThemeColors, ThemeName};
+
+/// User settings, read from `config.json` in the platform's config directory.
+#[derive(Deserialize)]
+#[serde(default)]
+pub(crate) struct Config {
+    pub(crate) keys: KeyMap,
+    /// Shown between groups of three digits in displayed counts. Empty to turn grouping off.
+    pub(crate) thousands_separator: String,
+    pub(crate) duplicate_names: DuplicateNames,
+    /// Whether to capture the mouse, for clicking and scrolling the list.
+    pub(crate) mouse: bool,
+    /// How many backups of the save to keep. Each session backs it up before its first save.
+    pub(crate) backups: usize,
+    /// Whether deleting takes pressing the delete key twice, like `dd` in vim, rather than
+    /// confirming.
+    pub(crate) vim_delete: bool,
+    /// Whether adding and subtracting start with the counter's step filled in.
+    pub(crate) prefill_adding: bool,
+    /// Whether moving up from the first counter goes to the last, and down from the last to the
+    /// first.
+    pub(crate) wrap_navigation: bool,
+    /// Whether amounts typed while adding may have thousands separators, like `1,000`. With
+    /// `.` as the separator, `,` is read as the decimal point.
+    pub(crate) lenient_numbers: bool,
+    /// Whether 1 to 9 in Normal mode add to the selected counter straight away.
+    pub(crate) quick_add: bool,
+    /// Amounts for the quick add keys in place of their face value, as numbers or expressions
+    /// like in Adding mode.
+    pub(crate) quick_amounts: HashMap<char, String>,
+    /// Drawn in front of the selected row. Empty for none.
+    pub(crate) highlight_symbol: String,
+    /// Whether rows only make room for the highlight symbol while one is selected, instead of
+    /// always keeping the column free.
+    pub(crate) compact_list: bool,
+    /// Whether the time and how long the session has run are shown at the start, in the top
+    /// right corner of the list.
+    pub(crate) clock: bool,
+    pub(crate) theme: ThemeName,
+    /// Colors to use in place of the theme's.
+    pub(crate) colors: ThemeColors,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            keys: KeyMap::default(),
+            thousands_separator: ",".to_owned(),
+            duplicate_names: DuplicateNames::default(),
+            mouse: true,
+            backups: 3,
+            vim_delete: false,
+            prefill_adding: false,
+            wrap_navigation: false,
+            lenient_numbers: false,
+            quick_add: false,
+            quick_amounts: HashMap::new(),
+            highlight_symbol: ">".to_owned(),
+            compact_list: false,
+            clock: true,
+            theme: ThemeName::default(),
+            colors: ThemeColors::default(),
+        }
+    }
+}
+
+impl Config {
+    fn path() -> Option<PathBuf> {
+        let mut path = dirs::config_dir()?;
+        path.push("tui-counters");
+        path.push("config.json");
+        Some(path)
+    }
+
+    /// Loads the config file, falling back to the defaults if it's missing or malformed.
+    pub(crate) fn load() -> Self {
+        let mut config: Self = Self::path()
+            .and_then(|path| File::open(path).ok())
+            .and_then(|file| serde_json::from_reader(file).ok())
+            .unwrap_or_default();
+        // A line break in the symbol would push the selected row's text onto the next line.
+        config.highlight_symbol.retain(|char| !char.is_control());
+        config
+    }
+}
+
+/// What happens when a counter is created or renamed with a name another counter already has.
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum DuplicateNames {
+    #[default]
+    Allow,
+    /// Keep the input open until the name is changed.
+    Reject,
+    /// Append a number, like `name (2)`.
+    Suffix,
+}
+
+/// The keys bound to each action in Normal mode.
+#[derive(Deserialize)]
+#[serde(default)]
+pub(crate) struct KeyMap {
+    pub(crate) increment: KeyBindings,
+    pub(crate) decrement: KeyBindings,
+    pub(crate) new: KeyBindings,
+    pub(crate) delete: KeyBindings,
+    pub(crate) quit: KeyBindings,
+    pub(crate) add: KeyBindings,
+    pub(crate) subtract: KeyBindings,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self {
+            increment: KeyBindings::new(&[KeyCode::Right, KeyCode::Char('l')]),
+            decrement: KeyBindings::new(&[KeyCode::Left, KeyCode::Char(';')]),
+            new: KeyBindings::new(&[KeyCode::Char('n')]),
+            delete: KeyBindings::new(&[KeyCode::Char('d')]),
+            quit: KeyBindings::new(&[KeyCode::Char('q')]),
+            add: KeyBindings::new(&[KeyCode::Char('a')]),
+            subtract: KeyBindings::new(&[KeyCode::Char('s')]),
+        }
+    }
+}
+
+/// The keys bound to one action, written in the config as a list like `["Right", "l"]`.
+#[derive(Deserialize)]
+pub(crate) struct KeyBindings(Vec<KeyBinding>);
+
+impl KeyBindings {
+    fn new(codes: &[KeyCode]) -> Self {
+        Self(codes.iter().map(|code| KeyBinding { code: *code, modifiers: KeyModifiers::NONE }).collect())
+    }
+
+    pub(crate) fn matches(&self, key: &KeyEvent) -> bool {
+        self.0.iter().any(|binding| binding.matches(key))
+    }
+}
+
+impl fmt::Display for KeyBindings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, binding) in self.0.iter().enumerate() {
+            if index > 0 {
+                write!(f, "/")?;
+            }
+            write!(f, "{}", binding)?;
+        }
+        Ok(())
+    }
+}
+
+/// A key with its modifiers, written like `l`, `Right` or `Ctrl+r`.
+#[derive(Deserialize)]
+#[serde(try_from = "String")]
+struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    fn matches(&self, key: &KeyEvent) -> bool {
+        // Terminals differ in whether they report shift for characters like `:` or `K`, so it
+        // only has to match for non-character keys.
+        let ignored = match key.code {
+            KeyCode::Char(_) => KeyModifiers::SHIFT,
+            _ => KeyModifiers::NONE,
+        };
+        key.code == self.code && key.modifiers.difference(ignored) == self.modifiers.difference(ignored)
+    }
+}
+
+impl TryFrom<String> for KeyBinding {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let mut parts: Vec<&str> = value.split('+').collect();
+        // A trailing `+` is the plus key itself, as in `Ctrl++`.
+        let key = match parts.pop() {
+            Some("") if value.ends_with('+') => {
+                parts.pop();
+                "+"
+            }
+            Some(key) => key,
+            None => return Err(format!("Empty key binding: {}", value)),
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for part in parts {
+            modifiers |= match part.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "shift" => KeyModifiers::SHIFT,
+                "alt" => KeyModifiers::ALT,
+                _ => return Err(format!("Unknown modifier: {}", part)),
+            };
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(char), None) => KeyCode::Char(char),
+            _ => match key.to_lowercase().as_str() {
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "enter" => KeyCode::Enter,
+                "esc" => KeyCode::Esc,
+                "backspace" => KeyCode::Backspace,
+                "tab" => KeyCode::Tab,
+                "space" => KeyCode::Char(' '),
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "delete" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                name => match name.strip_prefix('f').and_then(|number| number.parse().ok()) {
+                    Some(number) => KeyCode::F(number),
+                    None => return Err(format!("Unknown key: {}", key)),
+                },
+            },
+        };
+
+        Ok(Self { code, modifiers })
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(char) => write!(f, "{}", char),
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::Left => write!(f, "←"),
+            KeyCode::Right => write!(f, "→"),
+            KeyCode::F(number) => write!(f, "F{}", number),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
@@ -0,0 +1,572 @@
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use chrono::{Local, NaiveDate};
+use ratatui::prelude::*;
+use ratatui::widgets::ListItem;
+use serde::{Deserialize, Serialize};
+
+/// The most decimal places a counter can have.
+pub(crate) const MAX_PRECISION: u32 = 4;
+
+/// How many adjustments each counter remembers.
+const LOG_LENGTH: usize = 10;
+
+/// How far back, in seconds, changes count towards a counter's rate.
+const RATE_WINDOW: i64 = 60;
+
+/// The colors cycled through for counters, after the default.
+const COLORS: [&str; 6] = ["red", "green", "yellow", "blue", "magenta", "cyan"];
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct Counter {
+    pub(crate) name: String,
+    pub(crate) count: i64,
+    /// How much a single increment or decrement changes the count.
+    #[serde(default = "default_step")]
+    pub(crate) step: i64,
+    /// Number of decimal places. `count` and `step` are stored scaled by `10^precision`, so a
+    /// count of 150 with a precision of 2 is displayed as 1.50.
+    #[serde(default)]
+    pub(crate) precision: u32,
+    /// A goal for the count, scaled like it.
+    #[serde(default)]
+    pub(crate) target: Option<i64>,
+    /// Bounds the count is kept within, scaled like it.
+    #[serde(default)]
+    pub(crate) min: Option<i64>,
+    #[serde(default)]
+    pub(crate) max: Option<i64>,
+    /// When the count last changed, in Unix seconds.
+    #[serde(default)]
+    pub(crate) last_modified: Option<i64>,
+    /// A color name like `red`, for grouping counters visually.
+    #[serde(default)]
+    pub(crate) color: Option<String>,
+    /// The category the counter is listed under.
+    #[serde(default)]
+    pub(crate) group: Option<String>,
+    /// Whether the count goes back to zero at the start of each day.
+    #[serde(default)]
+    pub(crate) reset_daily: bool,
+    /// The local date the count was last reset on, for `reset_daily`.
+    #[serde(default)]
+    pub(crate) last_reset: Option<NaiveDate>,
+    /// Whether the count is protected from changes, and the counter from being deleted.
+    #[serde(default)]
+    pub(crate) locked: bool,
+    /// The most recent changes to the count, oldest first.
+    #[serde(default)]
+    pub(crate) log: VecDeque<Adjustment>,
+    /// Set while the counter is a running stopwatch, to the Unix second its count was last
+    /// caught up to. Kept in the save, so time keeps counting while the app is closed.
+    #[serde(default)]
+    pub(crate) timer_started: Option<i64>,
+    /// The length in seconds if the counter is a countdown. The timer then takes seconds off
+    /// the count instead of adding them, and stops at zero.
+    #[serde(default)]
+    pub(crate) countdown: Option<i64>,
+    /// Every change in the last `RATE_WINDOW` seconds this session, for the rate. Unlike `log`
+    /// it isn't capped at a number of changes, so it isn't saved.
+    #[serde(skip)]
+    pub(crate) recent: VecDeque<Adjustment>,
+    /// When the count was last set this session. Finer than `last_modified`, to tell apart
+    /// changes in the same second when sorting by recent changes.
+    #[serde(skip)]
+    pub(crate) changed_at: Option<Instant>,
+    /// How the count is shown in the list. Only the whole part is shown in formats other than
+    /// decimal.
+    #[serde(default)]
+    pub(crate) display_format: DisplayFormat,
+    /// Whether the counter is listed before the others, whatever the sort.
+    #[serde(default)]
+    pub(crate) pinned: bool,
+    /// Free text about the counter, like what it tracks.
+    #[serde(default)]
+    pub(crate) note: Option<String>,
+    /// Labels for filtering the list with `#tag`. Unlike the group, a counter can have several.
+    #[serde(default)]
+    pub(crate) tags: Vec<String>,
+    /// How much each of the count is worth in the weighted total, like 3 points per item.
+    #[serde(default = "default_weight")]
+    pub(crate) weight: f64,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum DisplayFormat {
+    #[default]
+    Decimal,
+    Hex,
+    Binary,
+    /// The count as seconds, like `01:02:03`.
+    Duration,
+}
+
+impl DisplayFormat {
+    pub(crate) fn next(self) -> Self {
+        match self {
+            DisplayFormat::Decimal => DisplayFormat::Hex,
+            DisplayFormat::Hex => DisplayFormat::Binary,
+            DisplayFormat::Binary => DisplayFormat::Duration,
+            DisplayFormat::Duration => DisplayFormat::Decimal,
+        }
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            DisplayFormat::Decimal => "decimal",
+            DisplayFormat::Hex => "hex",
+            DisplayFormat::Binary => "binary",
+            DisplayFormat::Duration => "duration",
+        }
+    }
+
+    /// Formats a value scaled by `10^precision`, with `separator` between thousands in decimal.
+    fn format(self, value: i64, precision: u32, separator: &str) -> String {
+        let whole = value / 10i64.pow(precision);
+        let sign = if whole < 0 { "-" } else { "" };
+        let magnitude = whole.unsigned_abs();
+        match self {
+            DisplayFormat::Decimal => format_grouped(value, precision, separator),
+            DisplayFormat::Hex => format!("{}0x{:X}", sign, magnitude),
+            DisplayFormat::Binary => format!("{}0b{:b}", sign, magnitude),
+            DisplayFormat::Duration => {
+                format!("{}{:02}:{:02}:{:02}", sign, magnitude / 3600, magnitude / 60 % 60, magnitude % 60)
+            }
+        }
+    }
+}
+
+/// A change to a counter's count.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct Adjustment {
+    /// How much the count changed, scaled like it.
+    pub(crate) delta: i64,
+    /// When it changed, in Unix seconds.
+    pub(crate) time: i64,
+}
+
+impl Adjustment {
+    fn with_precision(&self, from: u32, to: u32) -> Self {
+        Self { delta: rescale(self.delta, from, to), ..*self }
+    }
+}
+
+fn default_step() -> i64 {
+    1
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+impl Counter {
+    pub(crate) fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            count: 0,
+            step: default_step(),
+            precision: 0,
+            target: None,
+            min: None,
+            max: None,
+            last_modified: None,
+            color: None,
+            group: None,
+            reset_daily: false,
+            last_reset: None,
+            locked: false,
+            log: VecDeque::new(),
+            timer_started: None,
+            countdown: None,
+            recent: VecDeque::new(),
+            changed_at: None,
+            display_format: DisplayFormat::default(),
+            pinned: false,
+            note: None,
+            tags: Vec::new(),
+            weight: default_weight(),
+        }
+    }
+
+    /// Whether the counter is shown under a lowercased filter: `#tag` matches counters with that
+    /// tag, and anything else matches part of the name.
+    pub(crate) fn matches(&self, filter: &str) -> bool {
+        match filter.strip_prefix('#') {
+            Some(tag) => tag.is_empty() || self.tags.iter().any(|own| own.to_lowercase() == tag),
+            None => self.name.to_lowercase().contains(filter),
+        }
+    }
+
+    /// Moves on to the next color, going back to the default after the last one.
+    pub(crate) fn cycle_color(&mut self) {
+        let next = match &self.color {
+            None => Some(0),
+            Some(color) => COLORS.iter().position(|name| name == color).map(|index| index + 1),
+        };
+        self.color = next.and_then(|index| COLORS.get(index)).map(|name| name.to_string());
+    }
+
+    pub(crate) fn set_count(&mut self, count: i64) {
+        let delta = count.saturating_sub(self.count);
+        self.count = count;
+        self.last_modified = Some(unix_time());
+        self.changed_at = Some(Instant::now());
+
+        if delta != 0 {
+            let now = unix_time();
+            self.log.push_back(Adjustment { delta, time: now });
+            if self.log.len() > LOG_LENGTH {
+                self.log.pop_front();
+            }
+            self.recent.retain(|adjustment| now - adjustment.time < RATE_WINDOW);
+            self.recent.push_back(Adjustment { delta, time: now });
+        }
+    }
+
+    /// How much the count changed over the last minute, like `+12/min`, or `None` if it hasn't.
+    fn rate(&self) -> Option<String> {
+        let now = unix_time();
+        let change: i64 = self
+            .recent
+            .iter()
+            .filter(|adjustment| now - adjustment.time < RATE_WINDOW)
+            .map(|adjustment| adjustment.delta)
+            .fold(0, i64::saturating_add);
+        if change == 0 {
+            return None;
+        }
+        let sign = if change > 0 { "+" } else { "" };
+        Some(format!("{}{}/min", sign, format_amount(change, self.precision)))
+    }
+
+    /// A count moved inside the counter's bounds.
+    pub(crate) fn clamp(&self, count: i64) -> i64 {
+        let count = self.max.map_or(count, |max| count.min(max));
+        self.min.map_or(count, |min| count.max(min))
+    }
+
+    /// Turns resetting each day on or off. It counts as reset today, so the current count is
+    /// kept until tomorrow.
+    pub(crate) fn toggle_reset_daily(&mut self) {
+        self.reset_daily = !self.reset_daily;
+        self.last_reset = self.reset_daily.then(today);
+    }
+
+    /// Starts the stopwatch, or stops it after counting the time so far. A countdown that has
+    /// finished starts over.
+    pub(crate) fn toggle_timer(&mut self) {
+        self.catch_up_timer();
+        if self.timer_started.is_none() && self.countdown.is_some() && self.count <= 0 {
+            self.reset_countdown();
+        }
+        self.timer_started = match self.timer_started {
+            Some(_) => None,
+            None => Some(unix_time()),
+        };
+    }
+
+    /// Adds the whole seconds since the stopwatch was last caught up to the count, or takes them
+    /// off a countdown, stopping it at zero. The seconds aren't logged, since there would be one
+    /// entry for each. Returns whether the count changed.
+    pub(crate) fn catch_up_timer(&mut self) -> bool {
+        let Some(started) = self.timer_started else {
+            return false;
+        };
+        let now = unix_time();
+        let seconds = now - started;
+        if seconds <= 0 {
+            return false;
+        }
+        let change = seconds.saturating_mul(10i64.pow(self.precision));
+        if self.countdown.is_some() {
+            self.count = self.count.saturating_sub(change).max(0);
+            self.timer_started = (self.count > 0).then_some(now);
+        }
+        else {
+            self.count = self.clamp(self.count.saturating_add(change));
+            self.timer_started = Some(now);
+        }
+        self.last_modified = Some(now);
+        true
+    }
+
+    /// Stops a countdown and sets it back to its full length.
+    pub(crate) fn reset_countdown(&mut self) {
+        if let Some(seconds) = self.countdown {
+            self.count = seconds.saturating_mul(10i64.pow(self.precision));
+            self.timer_started = None;
+        }
+    }
+
+    /// Zeroes the count if it resets daily and hasn't been reset today. Returns whether it did.
+    pub(crate) fn reset_if_new_day(&mut self) -> bool {
+        let today = today();
+        if !self.reset_daily || self.last_reset.is_some_and(|last_reset| last_reset >= today) {
+            return false;
+        }
+        self.set_count(0);
+        self.last_reset = Some(today);
+        true
+    }
+
+    fn reached_target(&self) -> bool {
+        self.target.is_some_and(|target| self.count >= target)
+    }
+
+    /// Everything known about the counter, for the detail view.
+    pub(crate) fn detail_lines(&self) -> Vec<Line<'_>> {
+        let format_optional = |value: Option<i64>| value.map_or("none".to_owned(), |value| format_amount(value, self.precision));
+        let last_modified = self.last_modified.map_or("never".to_owned(), |last_modified| format_ago(unix_time() - last_modified));
+        let mut lines = vec![
+            Line::raw(format!("Name: {}", self.name)),
+            Line::raw(format!("Value: {}", format_amount(self.count, self.precision))),
+            Line::raw(format!("Step: {}", format_amount(self.step, self.precision))),
+            Line::raw(format!("Target: {}", format_optional(self.target))),
+            Line::raw(format!("Minimum: {}", format_optional(self.min))),
+            Line::raw(format!("Maximum: {}", format_optional(self.max))),
+            Line::raw(format!("Last modified: {}", last_modified)),
+            Line::raw(format!("Rate: {}", self.rate().unwrap_or_else(|| "none".to_owned()))),
+            Line::raw(format!("Weight: {}", format_weighted(self.weight))),
+            Line::raw(format!("Resets daily: {}", if self.reset_daily { "yes" } else { "no" })),
+            Line::raw(format!("Shown as: {}", self.display_format.name())),
+            Line::raw(match self.countdown {
+                Some(seconds) => format!("Countdown: {} of {}", if self.timer_started.is_some() { "running" } else { "paused" }, DisplayFormat::Duration.format(seconds, 0, "")),
+                None => format!("Stopwatch: {}", if self.timer_started.is_some() { "running" } else { "stopped" }),
+            }),
+        ];
+        if !self.tags.is_empty() {
+            let mut spans = vec![Span::raw("Tags:")];
+            for tag in &self.tags {
+                spans.push(Span::raw(" "));
+                spans.push(Span::raw(format!(" {} ", tag)).reversed());
+            }
+            lines.push(Line::from(spans));
+        }
+        if let Some(note) = &self.note {
+            lines.push(Line::raw(""));
+            lines.push(Line::raw("Note:"));
+            lines.extend(note.lines().map(|line| Line::raw(line.to_owned())));
+        }
+        if !self.log.is_empty() {
+            lines.push(Line::raw(""));
+            lines.push(Line::raw("Recent changes:"));
+            for adjustment in self.log.iter().rev() {
+                let sign = if adjustment.delta > 0 { "+" } else { "" };
+                let delta = format!("{}{}", sign, format_amount(adjustment.delta, self.precision));
+                lines.push(Line::raw(format!("  {}  {}", delta, format_ago(unix_time() - adjustment.time))));
+            }
+        }
+        lines
+    }
+
+    /// The count as displayed, for comparing counters with different precisions.
+    pub(crate) fn value(&self) -> f64 {
+        self.count as f64 / 10f64.powi(self.precision as i32)
+    }
+
+    /// A copy of this counter with its scaled values rescaled to a new precision. Lowering the
+    /// precision truncates the dropped decimals.
+    pub(crate) fn with_precision(&self, precision: u32) -> Self {
+        Self {
+            count: rescale(self.count, self.precision, precision),
+            step: rescale(self.step, self.precision, precision).max(1),
+            target: self.target.map(|target| rescale(target, self.precision, precision)),
+            min: self.min.map(|min| rescale(min, self.precision, precision)),
+            max: self.max.map(|max| rescale(max, self.precision, precision)),
+            log: self.log.iter().map(|adjustment| adjustment.with_precision(self.precision, precision)).collect(),
+            recent: self.recent.iter().map(|adjustment| adjustment.with_precision(self.precision, precision)).collect(),
+            precision,
+            ..self.clone()
+        }
+    }
+
+    /// The counter's row in the list, with `separator` between thousands in the count, and its
+    /// share of the total as a fraction if there is one to show.
+    pub(crate) fn list_item(&self, separator: &str, share: Option<f64>) -> ListItem<'static> {
+        let format = |value| self.display_format.format(value, self.precision, separator);
+        let count = format(self.count);
+        let progress = match self.target {
+            Some(target) if target != 0 => {
+                let percent = (self.count as f64 / target as f64 * 100.0).floor();
+                format!("{}/{} ({}%)", count, format(target), percent)
+            }
+            Some(target) => format!("{}/{}", count, format(target)),
+            None => count,
+        };
+        let progress = match share {
+            Some(share) => format!("{} ({:.0}% of total)", progress, share * 100.0),
+            None => progress,
+        };
+        // Without a color of its own, the text takes the theme's color from the list.
+        let default_color = self.reached_target().then_some(Color::Green);
+        let color = self.color.as_deref().and_then(|color| Color::from_str(color).ok()).or(default_color);
+        let mut style = color.map_or(Style::new(), |color| Style::new().fg(color));
+        if self.reached_target() {
+            style = style.bold();
+        }
+        let mut line = Line::styled(format!("{}: {}", progress, self.name), style);
+        if self.pinned {
+            line.push_span(Span::raw(" 📌"));
+        }
+        if self.locked {
+            line.push_span(Span::raw(" 🔒"));
+        }
+        if self.timer_started.is_some() {
+            line.push_span(Span::raw(if self.countdown.is_some() { " ⏳" } else { " ⏱" }));
+        }
+        if let Some(last_modified) = self.last_modified {
+            line.push_span(Span::raw(format!("  {}", format_ago(unix_time() - last_modified))).dim());
+        }
+        if let Some(rate) = self.rate() {
+            line.push_span(Span::raw(format!("  {}", rate)).dim());
+        }
+
+        ListItem::new(line)
+    }
+}
+
+/// The current time in Unix seconds.
+pub(crate) fn unix_time() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() as i64)
+}
+
+/// The current local date.
+fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+/// Formats an age in seconds like `5m ago`.
+fn format_ago(seconds: i64) -> String {
+    match seconds.max(0) {
+        seconds if seconds < 60 => format!("{}s ago", seconds),
+        seconds if seconds < 60 * 60 => format!("{}m ago", seconds / 60),
+        seconds if seconds < 60 * 60 * 24 => format!("{}h ago", seconds / (60 * 60)),
+        seconds => format!("{}d ago", seconds / (60 * 60 * 24)),
+    }
+}
+
+/// Sum of some counters, saturating like the counters themselves, along with its precision: the
+/// largest precision of any of them.
+pub(crate) fn total(counters: &[&Counter]) -> (i64, u32) {
+    let precision = counters.iter().map(|counter| counter.precision).max().unwrap_or(0);
+    let total = counters.iter().fold(0i64, |total, counter| {
+        total.saturating_add(rescale(counter.count, counter.precision, precision))
+    });
+    (total, precision)
+}
+
+/// The sum of every counter's value times its weight.
+pub(crate) fn weighted_total(counters: &[Counter]) -> f64 {
+    counters.iter().map(|counter| counter.value() * counter.weight).sum()
+}
+
+/// Formats a weight or weighted value with up to `MAX_PRECISION` decimals, leaving off trailing
+/// zeros, so `3.0` is shown as `3`.
+pub(crate) fn format_weighted(value: f64) -> String {
+    let text = format!("{:.*}", MAX_PRECISION as usize, value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" { "0".to_owned() } else { text.to_owned() }
+}
+
+/// Converts a value scaled by `10^from` to one scaled by `10^to`, saturating on overflow.
+pub(crate) fn rescale(value: i64, from: u32, to: u32) -> i64 {
+    if to >= from {
+        value.saturating_mul(10i64.pow(to - from))
+    }
+    else {
+        value / 10i64.pow(from - to)
+    }
+}
+
+/// Multiplies or divides a scaled value by a factor scaled the same way, rounding toward zero
+/// and saturating at the `i64` bounds. Returns `None` when dividing by zero.
+pub(crate) fn scale_amount(value: i64, factor: i64, precision: u32, divide: bool) -> Option<i64> {
+    let scale = 10i128.pow(precision);
+    let result = if divide {
+        (i128::from(value) * scale).checked_div(i128::from(factor))?
+    }
+    else {
+        i128::from(value) * i128::from(factor) / scale
+    };
+    Some(result.clamp(i128::from(i64::MIN), i128::from(i64::MAX)) as i64)
+}
+
+/// Formats a scaled value with `precision` decimal places.
+pub(crate) fn format_amount(value: i64, precision: u32) -> String {
+    if precision == 0 {
+        return value.to_string();
+    }
+    let scale = 10u64.pow(precision);
+    let magnitude = value.unsigned_abs();
+    let sign = if value < 0 { "-" } else { "" };
+    format!("{sign}{}.{:0width$}", magnitude / scale, magnitude % scale, width = precision as usize)
+}
+
+/// Formats a scaled value like `format_amount`, with `separator` between each group of three
+/// digits before the decimal point, as in `1,000,000.50`. Only for display, since
+/// `parse_amount` doesn't accept separators.
+pub(crate) fn format_grouped(value: i64, precision: u32, separator: &str) -> String {
+    let amount = format_amount(value, precision);
+    if separator.is_empty() {
+        return amount;
+    }
+    let (sign, digits) = match amount.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", amount.as_str()),
+    };
+    let (whole, fraction) = match digits.split_once('.') {
+        Some((whole, fraction)) => (whole, format!(".{}", fraction)),
+        None => (digits, String::new()),
+    };
+
+    let mut grouped = String::new();
+    for (index, digit) in whole.chars().enumerate() {
+        if index > 0 && (whole.len() - index) % 3 == 0 {
+            grouped.push_str(separator);
+        }
+        grouped.push(digit);
+    }
+    format!("{sign}{grouped}{fraction}")
+}
+
+/// Parses a decimal like `-1.5` into a value scaled by `10^precision`. Returns `None` for
+/// anything that isn't a number or has more decimals than `precision`. Values beyond `i64`
+/// saturate, the same way counts do.
+pub(crate) fn parse_amount(text: &str, precision: u32) -> Option<i64> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        return None;
+    }
+    if fraction.len() > precision as usize || !whole.chars().chain(fraction.chars()).all(|char| char.is_ascii_digit()) {
+        return None;
+    }
+
+    let parse_digits = |digits: &str| {
+        digits.bytes().fold(0i64, |value, digit| value.saturating_mul(10).saturating_add(i64::from(digit - b'0')))
+    };
+    let whole = parse_digits(whole).saturating_mul(10i64.pow(precision));
+    let fraction = parse_digits(fraction).saturating_mul(10i64.pow(precision - fraction.len() as u32));
+    let value = whole.saturating_add(fraction);
+
+    Some(if negative { -value } else { value })
+}
+
+/// Parses a length of time as seconds, like `90`, `25:00` or `1:30:00`. Returns `None` for
+/// anything else, or if it's zero.
+pub(crate) fn parse_duration(text: &str) -> Option<i64> {
+    let parts: Vec<&str> = text.split(':').collect();
+    if parts.len() > 3 || parts.iter().any(|part| part.is_empty() || !part.chars().all(|char| char.is_ascii_digit())) {
+        return None;
+    }
+    let seconds = parts.iter().try_fold(0i64, |seconds, part| seconds.checked_mul(60)?.checked_add(part.parse().ok()?))?;
+    (seconds > 0).then_some(seconds)
+}
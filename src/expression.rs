@@ -0,0 +1,87 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::counter::parse_amount;
+
+/// Evaluates an arithmetic expression like `3*4` or `(10+5)/2` into a value scaled by
+/// `10^precision`, the same way counts are stored. Supports `+`, `-`, `*`, `/`, parentheses and
+/// unary minus, with the usual precedence. Division rounds toward zero.
+///
+/// Returns `None` if the expression is malformed, divides by zero, or overflows.
+pub(crate) fn evaluate(text: &str, precision: u32) -> Option<i64> {
+    let mut parser = Parser {
+        chars: text.chars().peekable(),
+        scale: 10i128.pow(precision),
+        precision,
+    };
+    let value = parser.expression()?;
+    parser.skip_whitespace();
+    if parser.chars.peek().is_some() {
+        return None;
+    }
+    i64::try_from(value).ok()
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    scale: i128,
+    precision: u32,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|char| char.is_whitespace()).is_some() {}
+    }
+
+    fn next_operator(&mut self, operators: &[char]) -> Option<char> {
+        self.skip_whitespace();
+        self.chars.next_if(|char| operators.contains(char))
+    }
+
+    fn expression(&mut self) -> Option<i128> {
+        let mut value = self.term()?;
+        while let Some(operator) = self.next_operator(&['+', '-']) {
+            let rhs = self.term()?;
+            value = match operator {
+                '+' => value.checked_add(rhs)?,
+                _ => value.checked_sub(rhs)?,
+            };
+        }
+        Some(value)
+    }
+
+    fn term(&mut self) -> Option<i128> {
+        let mut value = self.factor()?;
+        while let Some(operator) = self.next_operator(&['*', '/']) {
+            let rhs = self.factor()?;
+            value = match operator {
+                '*' => value.checked_mul(rhs)? / self.scale,
+                _ => value.checked_mul(self.scale)?.checked_div(rhs)?,
+            };
+        }
+        Some(value)
+    }
+
+    fn factor(&mut self) -> Option<i128> {
+        self.skip_whitespace();
+        match self.chars.peek()? {
+            '-' => {
+                self.chars.next();
+                self.factor()?.checked_neg()
+            }
+            '(' => {
+                self.chars.next();
+                let value = self.expression()?;
+                self.next_operator(&[')'])?;
+                Some(value)
+            }
+            _ => {
+                let mut number = String::new();
+                while let Some(char) = self.chars.next_if(|char| char.is_ascii_digit() || *char == '.') {
+                    number.push(char);
+                }
+                parse_amount(&number, self.precision).map(i128::from)
+            }
+        }
+    }
+}
@@ -1,10 +1,17 @@
+use std::time::Duration;
+
 use clap::Parser;
 
 use crate::app::App;
 use crate::clap_arguments::Args;
-use crate::utils::{init_terminal, restore_terminal};
+use crate::utils::{confirm, init_terminal, restore_terminal};
 
 mod app;
+mod config;
+mod counter;
+mod expression;
+mod save_file;
+mod theme;
 mod utils;
 mod clap_arguments;
 
@@ -13,25 +20,73 @@ fn main() -> anyhow::Result<()> {
 
     let args = Args::parse();
 
-    let terminal = init_terminal()?;
+    if let (true, Some(input_name)) = (args.list, &args.input_name) {
+        print!("{}", App::open_existing(input_name)?.list());
+        return Ok(());
+    }
+
+    if let (true, Some(input_name)) = (args.restore, &args.input_name) {
+        let (backup, kept) = App::restore_backup(input_name)?;
+        println!("Restored {}", backup.display());
+        if let Some(kept) = kept {
+            println!("The save it replaced is kept at {}", kept.display());
+        }
+        return Ok(());
+    }
+
+    if let (Some(path), Some(input_name)) = (&args.import, &args.input_name) {
+        println!("{}", App::make_saved(input_name)?.import(path, args.replace)?);
+        return Ok(());
+    }
+
+    if let Some([name, value]) = args.set.as_deref() {
+        let mut app = match &args.input_name {
+            None => App::make_temporary(),
+            Some(input_name) => App::make_saved(input_name)?,
+        };
+        println!("{}", app.set_counter(name, value)?);
+        return Ok(());
+    }
 
     let mut app = match args.input_name {
         None => App::make_temporary(),
+        Some(input_name) if args.readonly => App::open_readonly(&input_name)?,
         Some(input_name) => match App::make_saved(&input_name) {
-            Ok(app) => {
-                app
-            }
-            Err(error) => {
-                restore_terminal()?;
-                return Err(error);
+            Ok(app) => app,
+            // A save that exists but can't be read, most likely after editing it by hand.
+            Err(error) if App::save_exists(&input_name) => {
+                println!("{:#}", error);
+                if !confirm("Move the file aside and start with an empty list?")? {
+                    return Ok(());
+                }
+                println!("Moved it to {}", App::set_aside(&input_name)?.display());
+                App::make_saved(&input_name)?
             }
+            Err(error) => return Err(error),
         },
     };
-    let final_message = app.run(terminal)?;
 
-    restore_terminal()?;
+    if let Err(error) = app.check_writable() {
+        println!("{:#}", error);
+        if !confirm("Continue without saving?")? {
+            return Ok(());
+        }
+        app.stop_saving();
+    }
+
+    let terminal = init_terminal(app.mouse_enabled(), args.stdout_json)?;
+    let final_message = app.run(terminal, args.idle_timeout.map(Duration::from_secs))?;
 
-    if !final_message.is_empty() {
+    restore_terminal(args.stdout_json)?;
+
+    // The JSON is only written after a clean exit, so anything reading it gets the whole list.
+    if args.stdout_json && final_message.is_empty() {
+        println!("{}", app.counters_json()?);
+    }
+    else if args.stdout_json {
+        eprintln!("{}", final_message);
+    }
+    else if !final_message.is_empty() {
         println!("{}", final_message);
     }
 
@@ -0,0 +1,270 @@
+use std::borrow::Cow;
+use std::fs;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::counter::{Counter, MAX_PRECISION};
+
+/// The version written to new saves. Bump it when a change needs a migration in `read`.
+const VERSION: u32 = 1;
+
+/// How a save file is encoded, going by its extension.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Format {
+    Json,
+    Toml,
+}
+
+impl Format {
+    /// The format for a path: TOML for `.toml` files, JSON for anything else.
+    pub(crate) fn of(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Format::Toml,
+            _ => Format::Json,
+        }
+    }
+
+    pub(crate) fn extension(self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Toml => "toml",
+        }
+    }
+}
+
+/// The contents of a save file. Borrows the counters when saving, so they don't have to be
+/// cloned on every write.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SaveFile<'a> {
+    #[serde(default)]
+    pub(crate) version: u32,
+    pub(crate) counters: Cow<'a, [Counter]>,
+    /// Counters put away from the list.
+    #[serde(default)]
+    pub(crate) archived: Cow<'a, [Counter]>,
+    /// Index of the counter that was selected when the file was saved.
+    #[serde(default)]
+    pub(crate) selected: Option<usize>,
+    /// A target for the total of every counter.
+    #[serde(default)]
+    pub(crate) goal: Option<Goal>,
+}
+
+/// An amount scaled by `10^precision`, like a count.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub(crate) struct Goal {
+    pub(crate) amount: i64,
+    pub(crate) precision: u32,
+}
+
+impl Default for SaveFile<'_> {
+    fn default() -> Self {
+        Self {
+            version: VERSION,
+            counters: Cow::Owned(vec![]),
+            archived: Cow::Owned(vec![]),
+            selected: None,
+            goal: None,
+        }
+    }
+}
+
+impl<'a> SaveFile<'a> {
+    pub(crate) fn new(counters: &'a [Counter], archived: &'a [Counter], selected: Option<usize>, goal: Option<Goal>) -> Self {
+        Self {
+            version: VERSION,
+            counters: Cow::Borrowed(counters),
+            archived: Cow::Borrowed(archived),
+            selected,
+            goal,
+        }
+    }
+
+    /// Reads a save file, migrating older formats to the current version.
+    ///
+    /// Saves from before versioning are version 0: either a bare array of counters, or this
+    /// struct without the `version` field.
+    pub(crate) fn read(path: &Path) -> anyhow::Result<SaveFile<'static>> {
+        let save_file: SaveFile = match Format::of(path) {
+            Format::Json => {
+                let text = fs::read_to_string(path).context(format!("Failed to open file: {}", path.display()))?;
+                let value: Value = serde_json::from_str(&text).context(format!("Failed to parse file: {}", path.display()))?;
+
+                // The text is parsed again rather than converting `value`, so that errors point
+                // at a line and column.
+                if value.is_array() {
+                    let counters: Vec<Counter> = serde_json::from_str(&text).context(format!("Failed to parse file: {}", path.display()))?;
+                    let save_file = SaveFile { counters: Cow::Owned(counters), ..Default::default() };
+                    save_file.validate().context(format!("Invalid save file: {}", path.display()))?;
+                    return Ok(save_file);
+                }
+
+                serde_json::from_str(&text).context(format!("Failed to parse file: {}", path.display()))?
+            }
+            // TOML saves were added after versioning, so there's nothing to migrate.
+            Format::Toml => {
+                let text = fs::read_to_string(path).context(format!("Failed to open file: {}", path.display()))?;
+                toml::from_str(&text).context(format!("Failed to parse file: {}", path.display()))?
+            }
+        };
+        if save_file.version > VERSION {
+            anyhow::bail!("{} was saved by a newer version (format {}, this version reads up to {})", path.display(), save_file.version, VERSION);
+        }
+        save_file.validate().context(format!("Invalid save file: {}", path.display()))?;
+        Ok(SaveFile { version: VERSION, ..save_file })
+    }
+
+    /// Checks for values the app can't work with, which can only come from editing the file by
+    /// hand.
+    fn validate(&self) -> anyhow::Result<()> {
+        for (index, counter) in self.counters.iter().enumerate() {
+            let describe = || format!("counter {} ('{}')", index + 1, counter.name);
+            if counter.name.trim().is_empty() {
+                anyhow::bail!("counter {} has an empty name", index + 1);
+            }
+            if counter.step <= 0 {
+                anyhow::bail!("{} has a step of {}, but it has to be positive", describe(), counter.step);
+            }
+            if counter.precision > MAX_PRECISION {
+                anyhow::bail!("{} has {} decimal places, but at most {} are supported", describe(), counter.precision, MAX_PRECISION);
+            }
+            if !counter.weight.is_finite() {
+                anyhow::bail!("{} has a weight of {}, but it has to be a number", describe(), counter.weight);
+            }
+        }
+        if let Some(goal) = self.goal.filter(|goal| goal.precision > MAX_PRECISION) {
+            anyhow::bail!("The goal has {} decimal places, but at most {} are supported", goal.precision, MAX_PRECISION);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn write(&self, path: &Path) -> anyhow::Result<()> {
+        // Write to a sibling file first and rename it over the save, so a crash mid-write can't
+        // leave a truncated save behind.
+        let temp_path = temp_path(path);
+
+        let file = File::create(&temp_path).context(format!("Failed to open file: {}", temp_path.display()))?;
+        let mut writer = BufWriter::new(file);
+        match Format::of(path) {
+            Format::Json => serde_json::to_writer_pretty(&mut writer, self).context(format!("Failed to write file: {}", temp_path.display()))?,
+            Format::Toml => {
+                let text = toml::to_string_pretty(self).context(format!("Failed to write file: {}", temp_path.display()))?;
+                writer.write_all(text.as_bytes()).context(format!("Failed to write file: {}", temp_path.display()))?;
+            }
+        }
+        let file = writer.into_inner().context(format!("Failed to write file: {}", temp_path.display()))?;
+        file.sync_all().context(format!("Failed to write file: {}", temp_path.display()))?;
+
+        fs::rename(&temp_path, path).context(format!("Failed to replace file: {}", path.display()))?;
+
+        Ok(())
+    }
+}
+
+/// The sibling file a save is written to before it replaces the save.
+fn temp_path(path: &Path) -> PathBuf {
+    let mut temp_path = path.to_owned().into_os_string();
+    temp_path.push(".tmp");
+    PathBuf::from(temp_path)
+}
+
+/// Checks that a save can be written, by making and removing the file it's written through,
+/// so a directory that can't be written to is found before the first save fails.
+pub(crate) fn probe_writable(path: &Path) -> anyhow::Result<()> {
+    let temp_path = temp_path(path);
+    File::create(&temp_path).context(format!("Can't write to {}", temp_path.display()))?;
+    fs::remove_file(&temp_path).context(format!("Failed to remove file: {}", temp_path.display()))?;
+    Ok(())
+}
+
+/// The path of a backup of a save: `name.json.bak` for the most recent, then `name.json.bak.1`,
+/// `name.json.bak.2` and so on for older ones.
+pub(crate) fn backup_path(path: &Path, age: usize) -> PathBuf {
+    let mut backup_path = path.to_owned().into_os_string();
+    backup_path.push(".bak");
+    if age > 0 {
+        backup_path.push(format!(".{}", age));
+    }
+    PathBuf::from(backup_path)
+}
+
+/// Copies a save to its most recent backup, shifting older backups along and dropping any beyond
+/// `count`.
+pub(crate) fn rotate_backups(path: &Path, count: usize) -> anyhow::Result<()> {
+    if count == 0 || !path.exists() {
+        return Ok(());
+    }
+    for age in (1..count).rev() {
+        let older = backup_path(path, age - 1);
+        if older.exists() {
+            let newer = backup_path(path, age);
+            fs::rename(&older, &newer).context(format!("Failed to replace file: {}", newer.display()))?;
+        }
+    }
+    let backup = backup_path(path, 0);
+    fs::copy(path, &backup).context(format!("Failed to write file: {}", backup.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    /// A path in the temp directory for a test to write to, unique to the test and the run.
+    fn temp_file(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("tui-counters-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn legacy_arrays_are_saved_in_the_versioned_format() {
+        let path = temp_file("legacy.json");
+        fs::write(&path, r#"[{"name": "pushups", "count": 12}]"#).unwrap();
+
+        let save_file = SaveFile::read(&path).unwrap();
+        assert_eq!(save_file.counters.len(), 1);
+        save_file.write(&path).unwrap();
+
+        let value: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(value["version"], 1);
+        assert_eq!(value["counters"][0]["name"], "pushups");
+        assert_eq!(value["counters"][0]["count"], 12);
+    }
+
+    #[test]
+    fn toml_saves_round_trip() {
+        let mut counter = Counter::new("laps");
+        counter.precision = 1;
+        counter.set_count(25);
+        counter.last_reset = NaiveDate::from_ymd_opt(2024, 5, 1);
+        counter.tags = vec!["sport".to_owned()];
+        let counters = [counter, Counter::new("other")];
+        let goal = Some(Goal { amount: 100, precision: 0 });
+
+        let path = temp_file("round-trip.toml");
+        SaveFile::new(&counters, &[], Some(1), goal).write(&path).unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        let save_file = SaveFile::read(&path).unwrap();
+        save_file.write(&path).unwrap();
+        let rewritten = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(written, rewritten);
+        let counter = &save_file.counters[0];
+        assert_eq!(counter.count, 25);
+        assert_eq!(counter.log.len(), 1);
+        assert_eq!(counter.log[0].delta, 25);
+        assert_eq!(counter.last_reset, NaiveDate::from_ymd_opt(2024, 5, 1));
+        assert_eq!(save_file.selected, Some(1));
+        assert!(save_file.goal.is_some_and(|goal| goal.amount == 100));
+    }
+}
@@ -0,0 +1,95 @@
+use std::str::FromStr;
+
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders};
+use serde::Deserialize;
+
+/// The colors the UI is drawn with.
+pub(crate) struct Theme {
+    pub(crate) name: ThemeName,
+    /// The color of counters and other text that doesn't have a color of its own.
+    pub(crate) text: Color,
+    /// Applied to the selected row, on top of its own style.
+    pub(crate) highlight: Style,
+    pub(crate) border: Color,
+    pub(crate) footer: Color,
+}
+
+/// The built in themes, picked with `theme` in the config.
+#[derive(Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ThemeName {
+    /// Light text for terminals with a dark background.
+    #[default]
+    Dark,
+    /// Dark text for terminals with a light background.
+    Light,
+}
+
+impl ThemeName {
+    pub(crate) fn next(self) -> Self {
+        match self {
+            ThemeName::Dark => ThemeName::Light,
+            ThemeName::Light => ThemeName::Dark,
+        }
+    }
+}
+
+/// Colors set in the config to use in place of the theme's, as names like `red` or hex codes
+/// like `#ff8800`.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct ThemeColors {
+    text: Option<String>,
+    /// A background color for the selected row.
+    highlight: Option<String>,
+    border: Option<String>,
+    footer: Option<String>,
+}
+
+impl Theme {
+    /// A built in theme, with any colors from the config in place of its own.
+    pub(crate) fn new(name: ThemeName, colors: &ThemeColors) -> Self {
+        let mut theme = match name {
+            ThemeName::Dark => Self {
+                name,
+                text: Color::White,
+                highlight: Style::new().bold().reversed(),
+                border: Color::Reset,
+                footer: Color::Reset,
+            },
+            ThemeName::Light => Self {
+                name,
+                text: Color::Black,
+                highlight: Style::new().bold().reversed(),
+                border: Color::DarkGray,
+                footer: Color::DarkGray,
+            },
+        };
+
+        let parse = |color: &Option<String>| color.as_deref().and_then(|color| Color::from_str(color).ok());
+        if let Some(text) = parse(&colors.text) {
+            theme.text = text;
+        }
+        if let Some(highlight) = parse(&colors.highlight) {
+            // Reversing would swap the background into the text color.
+            theme.highlight = Style::new().bold().bg(highlight);
+        }
+        if let Some(border) = parse(&colors.border) {
+            theme.border = border;
+        }
+        if let Some(footer) = parse(&colors.footer) {
+            theme.footer = footer;
+        }
+        theme
+    }
+
+    /// A bordered block with a centered title, the way every panel is drawn.
+    pub(crate) fn block<'a>(&self, title: impl Into<Line<'a>>) -> Block<'a> {
+        Block::new()
+            .title(title.into().centered())
+            .borders(Borders::all())
+            .border_set(symbols::border::ROUNDED)
+            .border_style(Style::new().fg(self.border))
+    }
+}
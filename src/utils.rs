@@ -1,20 +1,46 @@
 use std::io;
-use std::io::stdout;
+use std::io::{stderr, stdin, stdout, Write};
 
 use ratatui::backend::{Backend, CrosstermBackend};
+use ratatui::crossterm::event::{DisableMouseCapture, EnableMouseCapture};
 use ratatui::crossterm::terminal::{
     disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
 };
 use ratatui::crossterm::ExecutableCommand;
 use ratatui::Terminal;
 
-pub fn init_terminal() -> io::Result<Terminal<impl Backend>> {
-    stdout().execute(EnterAlternateScreen)?;
+/// Where the TUI is drawn: stdout, or stderr when stdout is kept for output to pipe elsewhere.
+fn output(on_stderr: bool) -> Box<dyn Write> {
+    if on_stderr {
+        Box::new(stderr())
+    }
+    else {
+        Box::new(stdout())
+    }
+}
+
+pub fn init_terminal(mouse: bool, on_stderr: bool) -> io::Result<Terminal<impl Backend + Write>> {
+    let mut output = output(on_stderr);
+    output.execute(EnterAlternateScreen)?;
+    if mouse {
+        output.execute(EnableMouseCapture)?;
+    }
     enable_raw_mode()?;
-    Terminal::new(CrosstermBackend::new(stdout()))
+    Terminal::new(CrosstermBackend::new(output))
 }
 
-pub fn restore_terminal() -> io::Result<()> {
-    stdout().execute(LeaveAlternateScreen)?;
+pub fn restore_terminal(on_stderr: bool) -> io::Result<()> {
+    let mut output = output(on_stderr);
+    output.execute(DisableMouseCapture)?;
+    output.execute(LeaveAlternateScreen)?;
     disable_raw_mode()
 }
+
+/// Asks a yes or no question on the terminal before the TUI starts. Anything but `y` is a no.
+pub fn confirm(question: &str) -> io::Result<bool> {
+    print!("{} [y/N] ", question);
+    stdout().flush()?;
+    let mut answer = String::new();
+    stdin().read_line(&mut answer)?;
+    Ok(answer.trim().eq_ignore_ascii_case("y"))
+}